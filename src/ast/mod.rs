@@ -1,10 +1,20 @@
 use std::fmt;
 
-use crate::lexer::token_kind::TokenKind;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use crate::lexer::{token_kind::TokenKind, types::Span};
+
+#[derive(Clone, Debug, PartialEq)]
+/// Statement, along with the source it was parsed from (excluding the trailing newline)
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
 
 #[derive(Clone, Debug, PartialEq)]
-/// Statement, which does not return a value and usually deals with state
-pub enum Stmt {
+/// Kind of statement, which does not return a value and usually deals with state
+pub enum StmtKind {
     FnDef {
         ident: String,
         params: Vec<String>,
@@ -18,9 +28,34 @@ pub enum Stmt {
     Print(Expr),
     FnCall(String),
     Pop,
+    Try {
+        body: Vec<Stmt>,
+        ident: String,
+        handler: Vec<Stmt>,
+    },
+    Throw(Expr),
+    Import {
+        path: String,
+        namespace: Option<String>,
+    },
+    Assert {
+        expr: Expr,
+        message: Option<Expr>,
+    },
+    AssertEq {
+        left: Expr,
+        right: Expr,
+        message: Option<Expr>,
+    },
 }
 
 impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for StmtKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -44,6 +79,40 @@ impl fmt::Display for Stmt {
                 Self::Print(expr) => format!("(print {})", expr),
                 Self::FnCall(ident) => format!("(call {})", ident),
                 Self::Pop => "pop".to_string(),
+                Self::Try {
+                    body,
+                    ident,
+                    handler,
+                } => format!(
+                    "(try ({}) (catch {} ({})))",
+                    body.iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    ident,
+                    handler
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
+                Self::Throw(expr) => format!("(throw {})", expr),
+                Self::Import { path, namespace } => match namespace {
+                    Some(namespace) => format!("(import {:?} {})", path, namespace),
+                    None => format!("(import {:?})", path),
+                },
+                Self::Assert { expr, message } => match message {
+                    Some(message) => format!("(assert {} {})", expr, message),
+                    None => format!("(assert {})", expr),
+                },
+                Self::AssertEq {
+                    left,
+                    right,
+                    message,
+                } => match message {
+                    Some(message) => format!("(assert_eq {} {} {})", left, right, message),
+                    None => format!("(assert_eq {} {})", left, right),
+                },
             }
         )
     }
@@ -63,6 +132,15 @@ pub enum Expr {
         op: TokenKind,
         expr: Box<Expr>,
     },
+    Field {
+        expr: Box<Expr>,
+        field: String,
+    },
+    Index {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Interpolation(Vec<StringPart>),
     Pop,
 }
 
@@ -76,19 +154,48 @@ impl fmt::Display for Expr {
                 Self::Literal(literal) => literal.to_string(),
                 Self::BinaryOp { op, lhs, rhs } => format!("({} {} {})", op, lhs, rhs),
                 Self::UnaryOp { op, expr } => format!("({} {})", op, expr),
+                Self::Field { expr, field } => format!("(. {} {})", expr, field),
+                Self::Index { expr, index } => format!("(index {} {})", expr, index),
+                Self::Interpolation(parts) => format!(
+                    "(format {})",
+                    parts
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
                 Self::Pop => "pop".to_string(),
             }
         )
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+/// A piece of an interpolated string literal
+pub enum StringPart {
+    Literal(String),
+    Expr(Expr),
+}
+
+impl fmt::Display for StringPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(string) => write!(f, "{:?}", string),
+            Self::Expr(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// Literal types
 pub enum Literal {
     String(String),
     Float(f64),
     Int(i64),
+    BigInt(BigInt),
+    Rational(BigRational),
     Bool(bool),
+    Nil,
 }
 
 impl fmt::Display for Literal {
@@ -98,9 +205,12 @@ impl fmt::Display for Literal {
             "{}",
             match self {
                 Self::String(string) => string.to_string(),
-                Self::Float(float) => float.to_string(),
+                Self::Float(float) => format!("{:?}", float),
                 Self::Int(int) => int.to_string(),
+                Self::BigInt(int) => int.to_string(),
+                Self::Rational(rational) => format!("{}r", rational),
                 Self::Bool(boolean) => boolean.to_string(),
+                Self::Nil => "nil".to_string(),
             }
         )
     }
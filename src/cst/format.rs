@@ -0,0 +1,213 @@
+use crate::{
+    lexer::token_kind::TokenKind,
+    parser::{ParseError, Parser},
+};
+
+use super::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// Indentation for each level of `begin`, `try` or `catch` body
+const INDENT: &str = "    ";
+
+/// Format `input`, failing with the parse error if it isn't valid
+///
+/// Bodies are indented, tokens are separated by single spaces except around brackets, field
+/// access, commas, colons and prefix operators, and runs of blank lines become a single blank
+/// line, none being kept at the start or end of a body. Comments are kept where they are, and
+/// line endings stay `\r\n` if the input uses them
+pub fn format(input: &str) -> Result<String, ParseError> {
+    Parser::new(input).parse()?;
+
+    let mut formatter = Formatter {
+        out: String::new(),
+        line: String::new(),
+        depth: 0,
+        newlines: 0,
+        blank_line: false,
+        newline: if input.contains("\r\n") { "\r\n" } else { "\n" },
+    };
+    formatter.block(&SyntaxNode::parse(input));
+    formatter.end_line();
+    Ok(formatter.out)
+}
+
+struct Formatter {
+    out: String,
+    /// The line being formatted, without indentation
+    line: String,
+    depth: usize,
+    /// The number of newlines since the last token written, including those of lines which
+    /// only have whitespace
+    newlines: usize,
+    /// Whether a blank line should come before the next line
+    blank_line: bool,
+    newline: &'static str,
+}
+
+impl Formatter {
+    fn block(&mut self, node: &SyntaxNode) {
+        self.newlines = 0;
+        self.blank_line = false;
+        // Blank lines before the first statement or comment of a body are dropped
+        let mut started = false;
+        for child in &node.children {
+            match child {
+                SyntaxElement::Node(stmt) => {
+                    started = true;
+                    self.stmt(stmt);
+                }
+                SyntaxElement::Token(token) => match token.kind {
+                    TokenKind::Newline if started => self.newline(token),
+                    TokenKind::Comment => {
+                        started = true;
+                        self.write(&token.text, true);
+                    }
+                    _ => (),
+                },
+            }
+        }
+        self.blank_line = false;
+    }
+
+    fn stmt(&mut self, node: &SyntaxNode) {
+        let mut spacing = Spacing::default();
+        for child in &node.children {
+            match child {
+                SyntaxElement::Node(block) => {
+                    self.end_line();
+                    self.depth += 1;
+                    self.block(block);
+                    self.depth -= 1;
+                }
+                SyntaxElement::Token(token) => match token.kind {
+                    TokenKind::Whitespace => (),
+                    TokenKind::Newline => {
+                        self.newline(token);
+                        spacing = Spacing::default();
+                    }
+                    kind => {
+                        let space = spacing.space_before(kind);
+                        self.write(&token.text, space);
+                    }
+                },
+            }
+        }
+    }
+
+    /// End the current line, with a blank line to follow if there have been several newlines
+    /// in a row, whether in one token or separated by whitespace
+    fn newline(&mut self, token: &SyntaxToken) {
+        self.end_line();
+        self.newlines += token.text.matches('\n').count();
+        if self.newlines > 1 {
+            self.blank_line = true;
+        }
+    }
+
+    fn write(&mut self, text: &str, space: bool) {
+        self.newlines = 0;
+        if self.line.is_empty() {
+            if self.blank_line && !self.out.is_empty() {
+                self.out.push_str(self.newline);
+            }
+            self.blank_line = false;
+        } else if space {
+            self.line.push(' ');
+        }
+        self.line.push_str(text);
+    }
+
+    fn end_line(&mut self) {
+        if !self.line.is_empty() {
+            self.out.push_str(&INDENT.repeat(self.depth));
+            self.out.push_str(&self.line);
+            self.out.push_str(self.newline);
+            self.line.clear();
+        }
+    }
+}
+
+/// Decides whether there is a space before each token of a line
+#[derive(Default)]
+struct Spacing {
+    prev: Option<TokenKind>,
+    /// Whether the next token starts an operand, so `-` and `~` are prefix operators
+    expect_operand: bool,
+    /// Whether the previous token was a prefix `-` or `~`, which go right before their operand
+    prefix: bool,
+    /// Whether the line so far is `set`, so the next identifier is the variable being set
+    set: bool,
+}
+
+impl Spacing {
+    fn space_before(&mut self, kind: TokenKind) -> bool {
+        use TokenKind::*;
+
+        let space = match (self.prev, kind) {
+            (_, Comment) => return true,
+            _ if self.prefix => false,
+            (_, RightParen | RightBracket | Comma | Colon | Dot) => false,
+            (Some(LeftParen | LeftBracket | Dot), _) => false,
+            // Indexing
+            (Some(prev), LeftBracket) if ends_operand(prev) => false,
+            _ => true,
+        };
+
+        self.prefix = self.expect_operand && matches!(kind, Minus | BitNot);
+        self.expect_operand = match kind {
+            Push | Print | Throw | Assert | AssertEq | LeftParen | LeftBracket | Comma | Not
+            | BitNot => true,
+            Ident if self.set => true,
+            kind => is_binary_operator(kind),
+        };
+        self.set = kind == Set;
+        self.prev = Some(kind);
+        space
+    }
+}
+
+fn ends_operand(kind: TokenKind) -> bool {
+    use TokenKind::*;
+
+    matches!(
+        kind,
+        Ident
+            | IntLit
+            | FloatLit
+            | RationalLit
+            | StringLit
+            | RawStringLit
+            | True
+            | False
+            | Nil
+            | Pop
+            | RightParen
+            | RightBracket
+    )
+}
+
+fn is_binary_operator(kind: TokenKind) -> bool {
+    use TokenKind::*;
+
+    matches!(
+        kind,
+        Or | And
+            | Equals
+            | NotEq
+            | Less
+            | Greater
+            | LessEq
+            | GreaterEq
+            | BitOr
+            | BitXor
+            | BitAnd
+            | ShiftLeft
+            | ShiftRight
+            | Plus
+            | Minus
+            | Multiply
+            | Divide
+            | IntDivide
+            | Modulo
+            | Power
+    )
+}
@@ -0,0 +1,154 @@
+pub mod format;
+
+use std::{fmt, iter::Peekable, vec::IntoIter};
+
+use crate::lexer::{token_kind::TokenKind, Lexer};
+
+/// Kind of node in the concrete syntax tree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeKind {
+    /// A whole file
+    Root,
+    /// A statement, including the newlines ending each of its lines
+    Stmt,
+    /// The body of a `begin`, `try` or `catch`
+    Block,
+}
+
+/// A token along with its source text, which may be whitespace or a comment
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+/// Concrete syntax tree, which unlike the AST keeps every character of the source including
+/// whitespace and comments, so that the source can be reproduced exactly, e.g. by a formatter
+///
+/// The tree only groups tokens into statements and blocks, and is built for any input, so the
+/// input should be parsed with the `Parser` first if it needs to be valid
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn parse(input: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut end = 0;
+        for token in Lexer::new(input) {
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+            // The lexer skips whitespace, so it is whatever is between tokens
+            if token.span.start > end {
+                tokens.push(SyntaxToken {
+                    kind: TokenKind::Whitespace,
+                    text: input[end..token.span.start].to_string(),
+                });
+            }
+            tokens.push(SyntaxToken {
+                kind: token.kind,
+                text: token.text(input).to_string(),
+            });
+            end = token.span.end;
+        }
+        if end < input.len() {
+            tokens.push(SyntaxToken {
+                kind: TokenKind::Whitespace,
+                text: input[end..].to_string(),
+            });
+        }
+
+        Builder {
+            tokens: tokens.into_iter().peekable(),
+        }
+        .block(NodeKind::Root, None)
+    }
+}
+
+/// The source text of the tree, exactly as it was parsed
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => write!(f, "{}", node)?,
+                SyntaxElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Builder {
+    tokens: Peekable<IntoIter<SyntaxToken>>,
+}
+
+impl Builder {
+    fn at(&mut self, kind: TokenKind) -> bool {
+        self.tokens.peek().map(|token| token.kind) == Some(kind)
+    }
+
+    /// Statements and the whitespace, comments and newlines between them, up until
+    /// `terminator`, which is left unconsumed
+    fn block(&mut self, kind: NodeKind, terminator: Option<TokenKind>) -> SyntaxNode {
+        let mut children = Vec::new();
+        while let Some(token) = self.tokens.peek() {
+            match token.kind {
+                kind if Some(kind) == terminator => break,
+                TokenKind::Whitespace | TokenKind::Comment | TokenKind::Newline => {
+                    children.push(SyntaxElement::Token(self.tokens.next().unwrap()));
+                }
+                _ => children.push(SyntaxElement::Node(self.stmt())),
+            }
+        }
+        SyntaxNode { kind, children }
+    }
+
+    fn stmt(&mut self) -> SyntaxNode {
+        let first = self.tokens.peek().map(|token| token.kind);
+        let mut children = self.line();
+        match first {
+            Some(TokenKind::Begin) => self.body(&mut children, TokenKind::End),
+            Some(TokenKind::Try) => {
+                self.body(&mut children, TokenKind::Catch);
+                self.body(&mut children, TokenKind::End);
+            }
+            _ => (),
+        }
+        SyntaxNode {
+            kind: NodeKind::Stmt,
+            children,
+        }
+    }
+
+    /// Add a block ending at `terminator` to `children`, followed by the line it starts
+    fn body(&mut self, children: &mut Vec<SyntaxElement>, terminator: TokenKind) {
+        children.push(SyntaxElement::Node(
+            self.block(NodeKind::Block, Some(terminator)),
+        ));
+        if self.at(terminator) {
+            children.extend(self.line());
+        }
+    }
+
+    /// The tokens up to and including the next newline
+    fn line(&mut self) -> Vec<SyntaxElement> {
+        let mut line = Vec::new();
+        for token in self.tokens.by_ref() {
+            let kind = token.kind;
+            line.push(SyntaxElement::Token(token));
+            if kind == TokenKind::Newline {
+                break;
+            }
+        }
+        line
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::BTreeSet,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+use stacc::{
+    ast::Stmt,
+    interpreter::{hook::Hook, Interpreter, RuntimeError},
+};
+
+use crate::{repl::parse_expr, run, unwrap};
+
+/// Usage of the commands given at the debugger's prompt
+const HELP: &str = "\
+step, s                  run until the next statement, stepping into calls
+next, n                  run until the next statement in this function, stepping over calls
+out, o                   run until the current function returns
+continue, c              run until a breakpoint
+break, b [<line>|<fn>]   set a breakpoint on a line or on calls to a function, or list them
+delete, d <line>|<fn>    remove a breakpoint
+print, p <expr>          evaluate an expression in the current call-frame
+vars, v                  show the current call-frame's variables
+stack                    show the current call-frame's stack
+frame, f                 show the current call-frame's variables and stack as a table
+where, w                 show the calls leading to the current statement
+list, l                  show the source around the current statement
+quit, q                  stop the program
+help, h                  show this message
+An empty line repeats the last command";
+
+/// Run `source` under the debugger, pausing before the first statement
+pub fn debug(mut interpreter: Interpreter, source: &str) {
+    // Ctrl-C pauses the program rather than stopping it
+    let pause = Arc::new(AtomicBool::new(false));
+    {
+        let pause = pause.clone();
+        unwrap(ctrlc::set_handler(move || {
+            pause.store(true, Ordering::SeqCst)
+        }));
+    }
+
+    println!("Debugging, type help for commands");
+    interpreter.set_hook(Box::new(Debugger {
+        lines: source.lines().map(str::to_string).collect(),
+        source: source.to_string(),
+        editor: unwrap(DefaultEditor::new()),
+        breakpoints: BTreeSet::new(),
+        fn_breakpoints: BTreeSet::new(),
+        mode: Mode::Step,
+        pause,
+        entered_breakpoint: false,
+        depth: 0,
+        line: 0,
+        calls: Vec::new(),
+        last_command: String::new(),
+    }));
+    let code = run(&mut interpreter, source);
+    match code {
+        0 => println!("Program finished"),
+        code => println!("Program exited with code {}", code),
+    }
+    process::exit(code);
+}
+
+/// When to next pause
+#[derive(Clone, Copy)]
+enum Mode {
+    Step,
+    /// Before a statement at or above the depth of the given call-frame
+    Next(usize),
+    /// Before a statement in the caller of the given call-frame
+    Out(usize),
+    Continue,
+}
+
+/// A call to a function of the main program, which is paused in
+struct Call {
+    name: String,
+    /// The line (0 based) of the `call` statement
+    line: usize,
+    depth: usize,
+}
+
+/// Pauses the main program at breakpoints or after each step, code from modules and the prelude
+/// is always stepped over
+struct Debugger {
+    source: String,
+    lines: Vec<String>,
+    editor: DefaultEditor,
+    /// Lines (0 based) to pause at
+    breakpoints: BTreeSet<usize>,
+    /// Functions to pause in when they are called
+    fn_breakpoints: BTreeSet<String>,
+    mode: Mode,
+    /// Set by Ctrl-C to pause before the next statement
+    pause: Arc<AtomicBool>,
+    /// Whether a function with a breakpoint has just been called
+    entered_breakpoint: bool,
+    /// The depth of the call-frame of the last statement
+    depth: usize,
+    /// The line (0 based) of the last statement of the main program
+    line: usize,
+    calls: Vec<Call>,
+    last_command: String,
+}
+
+impl Hook for Debugger {
+    fn before_stmt(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), RuntimeError> {
+        self.depth = interpreter.depth();
+        if interpreter.in_module() {
+            return Ok(());
+        }
+
+        self.line = stmt.span.get_line_and_column(&self.source).0;
+        let stop = match self.mode {
+            Mode::Step => true,
+            Mode::Next(depth) => self.depth <= depth,
+            Mode::Out(depth) => self.depth < depth,
+            Mode::Continue => false,
+        };
+        let entered_breakpoint = std::mem::take(&mut self.entered_breakpoint);
+        if stop
+            || entered_breakpoint
+            || self.breakpoints.contains(&self.line)
+            || self.pause.swap(false, Ordering::SeqCst)
+        {
+            self.prompt(interpreter)?;
+        }
+        Ok(())
+    }
+
+    fn enter_fn(&mut self, interpreter: &mut Interpreter, name: &str) -> Result<(), RuntimeError> {
+        // Native functions and those of modules don't have frames in the main program
+        if interpreter.depth() > self.depth && !interpreter.in_module() {
+            self.calls.push(Call {
+                name: name.to_string(),
+                line: self.line,
+                depth: interpreter.depth(),
+            });
+            if self.fn_breakpoints.contains(name) {
+                self.entered_breakpoint = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn exit_fn(&mut self, interpreter: &mut Interpreter, _name: &str) {
+        if let Some(call) = self.calls.last() {
+            if call.depth == interpreter.depth() {
+                self.calls.pop();
+            }
+        }
+    }
+}
+
+impl Debugger {
+    /// Show where the program is paused and run commands until one resumes it
+    fn prompt(&mut self, interpreter: &mut Interpreter) -> Result<(), RuntimeError> {
+        match self.calls.last() {
+            Some(call) => println!("Paused at line {} in {}", self.line + 1, call.name),
+            None => println!("Paused at line {}", self.line + 1),
+        }
+        self.print_line(self.line);
+
+        loop {
+            let input = match self.editor.readline("(debug) ") {
+                Ok(input) => input,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => return Err(RuntimeError::Interrupted),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return Err(RuntimeError::Interrupted);
+                }
+            };
+
+            let input = match input.trim() {
+                "" => self.last_command.clone(),
+                input => {
+                    let _ = self.editor.add_history_entry(input);
+                    input.to_string()
+                }
+            };
+            self.last_command = input.clone();
+
+            let (name, arg) = match input.split_once(char::is_whitespace) {
+                Some((name, arg)) => (name, arg.trim()),
+                None => (input.as_str(), ""),
+            };
+            let mode = match name {
+                "step" | "s" => Some(Mode::Step),
+                "next" | "n" => Some(Mode::Next(self.depth)),
+                "out" | "o" => Some(Mode::Out(self.depth)),
+                "continue" | "c" => Some(Mode::Continue),
+                _ => None,
+            };
+            if let Some(mode) = mode {
+                self.mode = mode;
+                return Ok(());
+            }
+
+            match name {
+                "" => (),
+                "break" | "b" if arg.is_empty() => self.list_breakpoints(),
+                "break" | "b" => self.set_breakpoint(arg),
+                "delete" | "d" => self.delete_breakpoint(arg),
+                "print" | "p" if arg.is_empty() => eprintln!("Usage: print <expr>"),
+                "print" | "p" => match parse_expr(arg) {
+                    Ok(expr) => match interpreter.eval_expr(&expr) {
+                        Ok(value) => println!("{}", value),
+                        Err(err) => eprintln!("{}", err),
+                    },
+                    Err(err) => eprintln!("{}", err),
+                },
+                "vars" | "v" => {
+                    let mut variables = interpreter.frame().0.into_iter().collect::<Vec<_>>();
+                    variables.sort_by(|a, b| a.0.cmp(&b.0));
+                    for (name, value) in variables {
+                        println!("{} = {}", name, value);
+                    }
+                }
+                "stack" => {
+                    for value in interpreter.frame().1.iter().rev() {
+                        println!("{}", value);
+                    }
+                }
+                "frame" | "f" => interpreter.print_frame(),
+                "where" | "w" => {
+                    let mut line = self.line;
+                    for call in self.calls.iter().rev() {
+                        println!("line {} in {}", line + 1, call.name);
+                        line = call.line;
+                    }
+                    println!("line {}", line + 1);
+                }
+                "list" | "l" => {
+                    let start = self.line.saturating_sub(3);
+                    let end = (self.line + 4).min(self.lines.len());
+                    for line in start..end {
+                        self.print_line(line);
+                    }
+                }
+                "quit" | "q" => return Err(RuntimeError::Interrupted),
+                "help" | "h" => println!("{}", HELP),
+                _ => eprintln!("Unknown command {}, try help", name),
+            }
+        }
+    }
+
+    /// Print a line (0 based) of the source with its number, marking the current line
+    fn print_line(&self, line: usize) {
+        if let Some(text) = self.lines.get(line) {
+            let marker = if line == self.line { ">" } else { " " };
+            let breakpoint = if self.breakpoints.contains(&line) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{}{}{:>4} | {}", marker, breakpoint, line + 1, text);
+        }
+    }
+
+    /// Set a breakpoint on a line number (1 based) or a function name
+    fn set_breakpoint(&mut self, target: &str) {
+        match target.parse::<usize>() {
+            Ok(line) if (1..=self.lines.len()).contains(&line) => {
+                self.breakpoints.insert(line - 1);
+                println!("Breakpoint set at line {}", line);
+            }
+            Ok(_) => eprintln!("There is no line {}", target),
+            Err(_) => {
+                self.fn_breakpoints.insert(target.to_string());
+                println!("Breakpoint set on calls to {}", target);
+            }
+        }
+    }
+
+    fn delete_breakpoint(&mut self, target: &str) {
+        let removed = match target.parse::<usize>() {
+            Ok(line) => self.breakpoints.remove(&line.wrapping_sub(1)),
+            Err(_) => self.fn_breakpoints.remove(target),
+        };
+        if !removed {
+            eprintln!("There is no breakpoint on {}", target);
+        }
+    }
+
+    fn list_breakpoints(&self) {
+        if self.breakpoints.is_empty() && self.fn_breakpoints.is_empty() {
+            println!("No breakpoints");
+        }
+        for line in &self.breakpoints {
+            println!("line {}", line + 1);
+        }
+        for name in &self.fn_breakpoints {
+            println!("function {}", name);
+        }
+    }
+}
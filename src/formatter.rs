@@ -0,0 +1,68 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use stacc::cst::format::format;
+
+use crate::{find_scripts, EXIT_IO_ERROR, EXIT_PARSE_ERROR};
+
+/// Format the `.stacc` files in `paths` (searching directories recursively) in place, or only
+/// list those which would change if `check` is set, returning the code to exit with
+///
+/// A path of `-` formats stdin to stdout
+pub fn fmt(paths: &[PathBuf], check: bool) -> i32 {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.as_os_str() == "-" {
+            files.push(path.clone());
+        } else if let Err(err) = find_scripts(path, &mut files) {
+            eprintln!("{}: {}", path.display(), err);
+            return EXIT_IO_ERROR;
+        }
+    }
+
+    let mut code = 0;
+    for file in &files {
+        let stdin = file.as_os_str() == "-";
+        let source = if stdin {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source).map(|_| source)
+        } else {
+            fs::read_to_string(file)
+        };
+        let source = match source {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}: {}", file.display(), err);
+                code = EXIT_IO_ERROR;
+                continue;
+            }
+        };
+
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}: {}", file.display(), err);
+                code = EXIT_PARSE_ERROR;
+                continue;
+            }
+        };
+
+        if check {
+            if formatted != source {
+                println!("Would reformat {}", file.display());
+                code = code.max(1);
+            }
+        } else if stdin {
+            print!("{}", formatted);
+        } else if formatted != source {
+            if let Err(err) = fs::write(file, formatted) {
+                eprintln!("{}: {}", file.display(), err);
+                code = EXIT_IO_ERROR;
+            }
+        }
+    }
+    code
+}
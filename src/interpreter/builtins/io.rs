@@ -0,0 +1,197 @@
+use std::{
+    convert::TryFrom,
+    env,
+    fs::{self, File},
+    io::{self, Read, Write},
+};
+
+use super::{Native, NativeResult};
+use crate::interpreter::{capabilities::Capabilities, value::Value, Interpreter, RuntimeError};
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "read_file",
+        params: &["path"],
+        func: read_file,
+    },
+    Native {
+        name: "write_file",
+        params: &["path", "contents"],
+        func: |interpreter, args| write(interpreter, args, "write_file", false),
+    },
+    Native {
+        name: "append_file",
+        params: &["path", "contents"],
+        func: |interpreter, args| write(interpreter, args, "append_file", true),
+    },
+    Native {
+        name: "remove_file",
+        params: &["path"],
+        func: remove_file,
+    },
+    Native {
+        name: "file_exists",
+        params: &["path"],
+        func: file_exists,
+    },
+    Native {
+        name: "list_dir",
+        params: &["path"],
+        func: list_dir,
+    },
+    Native {
+        name: "args",
+        params: &[],
+        func: args,
+    },
+    Native {
+        name: "env",
+        params: &["name"],
+        func: env_var,
+    },
+    Native {
+        name: "has_env",
+        params: &["name"],
+        func: has_env,
+    },
+    Native {
+        name: "exit",
+        params: &["code"],
+        func: exit,
+    },
+];
+
+fn io_error(function: &'static str, path: &str, err: std::io::Error) -> RuntimeError {
+    RuntimeError::Io {
+        function,
+        message: format!("{}: {}", path, err),
+    }
+}
+
+fn read_file(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().read_fs, "read_file", "read_fs")?;
+    let path = args[0].to_str()?;
+    let file = File::open(path).map_err(|err| io_error("read_file", path, err))?;
+    let len = file
+        .metadata()
+        .map_err(|err| io_error("read_file", path, err))?
+        .len();
+    interpreter
+        .limits()
+        .check_string_len(usize::try_from(len).unwrap_or(usize::MAX))?;
+
+    // The size of some files isn't known up front, so at most one byte more than the limit is
+    // read to tell whether they are too long
+    let mut bytes = Vec::new();
+    match interpreter.limits().max_string_len {
+        Some(limit) => file.take(limit as u64 + 1).read_to_end(&mut bytes),
+        None => (&file).read_to_end(&mut bytes),
+    }
+    .map_err(|err| io_error("read_file", path, err))?;
+    interpreter.limits().check_string_len(bytes.len())?;
+
+    let contents = String::from_utf8(bytes).map_err(|err| {
+        io_error(
+            "read_file",
+            path,
+            io::Error::new(io::ErrorKind::InvalidData, err),
+        )
+    })?;
+    Ok(Some(Value::String(contents)))
+}
+
+fn write(
+    interpreter: &mut Interpreter,
+    args: Vec<Value>,
+    function: &'static str,
+    append: bool,
+) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().write_fs, function, "write_fs")?;
+    let path = args[0].to_str()?;
+    let contents = args[1].to_str()?;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|err| io_error(function, path, err))?;
+    Ok(None)
+}
+
+fn remove_file(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(
+        interpreter.capabilities().write_fs,
+        "remove_file",
+        "write_fs",
+    )?;
+    let path = args[0].to_str()?;
+    fs::remove_file(path).map_err(|err| io_error("remove_file", path, err))?;
+    Ok(None)
+}
+
+fn file_exists(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().read_fs, "file_exists", "read_fs")?;
+    let path = args[0].to_str()?;
+    Ok(Some(Value::Bool(fs::metadata(path).is_ok())))
+}
+
+/// The names of the entries in a directory, sorted so that the order is deterministic
+fn list_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().read_fs, "list_dir", "read_fs")?;
+    let path = args[0].to_str()?;
+    let mut names = fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|err| io_error("list_dir", path, err))?;
+    names.sort();
+
+    interpreter.limits().check_list_len(names.len())?;
+    Ok(Some(Value::List(
+        names.into_iter().map(Value::String).collect(),
+    )))
+}
+
+fn args(interpreter: &mut Interpreter, _: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().env, "args", "env")?;
+    Ok(Some(Value::List(
+        interpreter
+            .args()
+            .iter()
+            .cloned()
+            .map(Value::String)
+            .collect(),
+    )))
+}
+
+fn env_var(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().env, "env", "env")?;
+    let name = args[0].to_str()?;
+    match env::var(name) {
+        Ok(value) => Ok(Some(Value::String(value))),
+        Err(err) => Err(RuntimeError::InvalidArgument {
+            function: "env",
+            message: format!("{}: {}", name, err),
+        }),
+    }
+}
+
+fn has_env(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().env, "has_env", "env")?;
+    Ok(Some(Value::Bool(env::var_os(args[0].to_str()?).is_some())))
+}
+
+/// Stop the program, leaving it to the host to actually exit
+fn exit(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Capabilities::require(interpreter.capabilities().exit, "exit", "exit")?;
+    let code = args[0].to_int()?;
+    let code = i32::try_from(code).map_err(|_| RuntimeError::InvalidArgument {
+        function: "exit",
+        message: format!("{} is not a valid exit code", code),
+    })?;
+    Err(RuntimeError::Exit { code })
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use serde_json::{Map, Number};
+
+use super::{Native, NativeResult};
+use crate::interpreter::{value::Value, Interpreter, RuntimeError};
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "json_parse",
+        params: &["text"],
+        func: json_parse,
+    },
+    Native {
+        name: "json_stringify",
+        params: &["value"],
+        func: |_, args| Ok(Some(Value::String(to_json(&args[0])?.to_string()))),
+    },
+    Native {
+        name: "json_pretty",
+        params: &["value"],
+        func: |_, args| {
+            let json = to_json(&args[0])?;
+            Ok(Some(Value::String(
+                serde_json::to_string_pretty(&json).unwrap(),
+            )))
+        },
+    },
+];
+
+fn json_parse(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    // The error message includes the line and column of malformed input
+    let json =
+        serde_json::from_str(args[0].to_str()?).map_err(|err| RuntimeError::InvalidArgument {
+            function: "json_parse",
+            message: err.to_string(),
+        })?;
+    Ok(Some(from_json(json)))
+}
+
+fn from_json(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(boolean) => Value::Bool(boolean),
+        serde_json::Value::Number(number) => from_json_number(&number),
+        serde_json::Value::String(string) => Value::String(string),
+        serde_json::Value::Array(array) => Value::List(array.into_iter().map(from_json).collect()),
+        serde_json::Value::Object(object) => Value::Map(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Integers of any size are kept exact, everything else becomes a float
+fn from_json_number(number: &Number) -> Value {
+    if let Some(int) = number.as_i64() {
+        return Value::Int(int);
+    }
+
+    let text = number.to_string();
+    match BigInt::from_str(&text) {
+        Ok(int) => Value::BigInt(int),
+        Err(_) => Value::Float(text.parse().unwrap_or(f64::INFINITY)),
+    }
+}
+
+/// Convert a value to JSON, failing for values with no JSON equivalent such as functions
+pub fn to_json(value: &Value) -> Result<serde_json::Value, RuntimeError> {
+    Ok(match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Bool(boolean) => serde_json::Value::Bool(*boolean),
+        Value::Int(int) => serde_json::Value::Number((*int).into()),
+        Value::BigInt(int) => {
+            serde_json::Value::Number(Number::from_str(&int.to_string()).unwrap())
+        }
+        Value::Rational(rational) => float_to_json(rational.to_f64().unwrap_or(f64::NAN))?,
+        Value::Float(float) => float_to_json(*float)?,
+        Value::String(string) => serde_json::Value::String(string.clone()),
+        Value::List(list) => {
+            serde_json::Value::Array(list.iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Value::Map(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), to_json(value)?)))
+                .collect::<Result<Map<_, _>, RuntimeError>>()?,
+        ),
+        Value::Function(_) | Value::Error(_) | Value::Module(_) => {
+            return Err(RuntimeError::CannotPerformOnType {
+                op: "JSON serialization",
+                typ: value.type_name(),
+            })
+        }
+    })
+}
+
+fn float_to_json(float: f64) -> Result<serde_json::Value, RuntimeError> {
+    Number::from_f64(float)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| RuntimeError::InvalidArgument {
+            function: "json_stringify",
+            message: format!("{:?} cannot be represented in JSON", float),
+        })
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use super::{Native, NativeResult};
+use crate::interpreter::{value::Value, Interpreter, RuntimeError};
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "new_map",
+        params: &[],
+        func: |_, _| Ok(Some(Value::Map(BTreeMap::new()))),
+    },
+    Native {
+        name: "keys",
+        params: &["map"],
+        func: |_, args| {
+            let keys = to_map(&args[0])?.keys().cloned().map(Value::String);
+            Ok(Some(Value::List(keys.collect())))
+        },
+    },
+    Native {
+        name: "values",
+        params: &["map"],
+        func: |_, args| {
+            Ok(Some(Value::List(
+                to_map(&args[0])?.values().cloned().collect(),
+            )))
+        },
+    },
+    Native {
+        name: "has_key",
+        params: &["map", "key"],
+        func: |_, args| {
+            let has_key = to_map(&args[0])?.contains_key(args[1].to_str()?);
+            Ok(Some(Value::Bool(has_key)))
+        },
+    },
+    Native {
+        name: "insert",
+        params: &["map", "key", "value"],
+        func: insert,
+    },
+    Native {
+        name: "remove",
+        params: &["map", "key"],
+        func: remove,
+    },
+];
+
+fn to_map(value: &Value) -> Result<&BTreeMap<String, Value>, RuntimeError> {
+    match value {
+        Value::Map(map) => Ok(map),
+        _ => Err(RuntimeError::WrongType {
+            expected: "map",
+            got: value.type_name(),
+        }),
+    }
+}
+
+/// A copy of `map` with `key` set to `value`
+fn insert(_: &mut Interpreter, mut args: Vec<Value>) -> NativeResult {
+    let value = args.pop().unwrap();
+    let key = args[1].to_str()?.to_string();
+    let mut map = to_map(&args[0])?.clone();
+    map.insert(key, value);
+    Ok(Some(Value::Map(map)))
+}
+
+/// A copy of `map` without `key`, which doesn't have to be present
+fn remove(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let mut map = to_map(&args[0])?.clone();
+    map.remove(args[1].to_str()?);
+    Ok(Some(Value::Map(map)))
+}
@@ -0,0 +1,182 @@
+use std::f64::consts;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, Signed};
+
+use super::{Native, NativeResult};
+use crate::interpreter::{value::Value, Interpreter, RuntimeError};
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "sqrt",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::sqrt),
+    },
+    Native {
+        name: "floor",
+        params: &["x"],
+        func: floor,
+    },
+    Native {
+        name: "ceil",
+        params: &["x"],
+        func: ceil,
+    },
+    Native {
+        name: "round",
+        params: &["x"],
+        func: round,
+    },
+    Native {
+        name: "abs",
+        params: &["x"],
+        func: abs,
+    },
+    Native {
+        name: "min",
+        params: &["a", "b"],
+        func: min,
+    },
+    Native {
+        name: "max",
+        params: &["a", "b"],
+        func: max,
+    },
+    Native {
+        name: "sin",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::sin),
+    },
+    Native {
+        name: "cos",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::cos),
+    },
+    Native {
+        name: "tan",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::tan),
+    },
+    Native {
+        name: "asin",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::asin),
+    },
+    Native {
+        name: "acos",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::acos),
+    },
+    Native {
+        name: "atan",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::atan),
+    },
+    Native {
+        name: "atan2",
+        params: &["y", "x"],
+        func: |_, args| {
+            Ok(Some(Value::Float(
+                args[0].to_number()?.atan2(args[1].to_number()?),
+            )))
+        },
+    },
+    Native {
+        name: "exp",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::exp),
+    },
+    Native {
+        name: "log",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::ln),
+    },
+    Native {
+        name: "log10",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::log10),
+    },
+    Native {
+        name: "log2",
+        params: &["x"],
+        func: |_, args| float_fn(&args[0], f64::log2),
+    },
+    Native {
+        name: "pi",
+        params: &[],
+        func: |_, _| Ok(Some(Value::Float(consts::PI))),
+    },
+    Native {
+        name: "inf",
+        params: &[],
+        func: |_, _| Ok(Some(Value::Float(f64::INFINITY))),
+    },
+    Native {
+        name: "nan",
+        params: &[],
+        func: |_, _| Ok(Some(Value::Float(f64::NAN))),
+    },
+];
+
+fn float_fn(x: &Value, func: fn(f64) -> f64) -> NativeResult {
+    Ok(Some(Value::Float(func(x.to_number()?))))
+}
+
+/// Round a number to an integer using `float` for floats and `rational` for rationals
+fn to_integer(
+    x: &Value,
+    function: &'static str,
+    float: fn(f64) -> f64,
+    rational: fn(&BigRational) -> BigRational,
+) -> NativeResult {
+    Ok(Some(match x {
+        Value::Int(_) | Value::BigInt(_) => x.clone(),
+        Value::Rational(x) => Value::from_bigint(rational(x).to_integer()),
+        _ => {
+            let x = x.to_number()?;
+            let int = BigInt::from_f64(float(x)).ok_or(RuntimeError::InvalidArgument {
+                function,
+                message: format!("{:?} cannot be converted to an integer", x),
+            })?;
+            Value::from_bigint(int)
+        }
+    }))
+}
+
+fn floor(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    to_integer(&args[0], "floor", f64::floor, |x| x.floor())
+}
+
+fn ceil(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    to_integer(&args[0], "ceil", f64::ceil, |x| x.ceil())
+}
+
+/// Round half away from zero
+fn round(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    to_integer(&args[0], "round", f64::round, |x| x.round())
+}
+
+fn abs(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(match &args[0] {
+        Value::Int(int) => match int.checked_abs() {
+            Some(int) => Value::Int(int),
+            None => Value::from_bigint(BigInt::from(*int).abs()),
+        },
+        Value::BigInt(int) => Value::from_bigint(int.abs()),
+        Value::Rational(rational) => Value::Rational(rational.abs()),
+        x => Value::Float(x.to_number()?.abs()),
+    }))
+}
+
+fn min(_: &mut Interpreter, mut args: Vec<Value>) -> NativeResult {
+    let b = args.pop().unwrap();
+    let a = args.pop().unwrap();
+    Ok(Some(if bool::from(b.lt(a.clone())?) { b } else { a }))
+}
+
+fn max(_: &mut Interpreter, mut args: Vec<Value>) -> NativeResult {
+    let b = args.pop().unwrap();
+    let a = args.pop().unwrap();
+    Ok(Some(if bool::from(b.gt(a.clone())?) { b } else { a }))
+}
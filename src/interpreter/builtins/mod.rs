@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use super::{value::Value, Interpreter, RuntimeError};
+
+pub mod io;
+pub mod json;
+pub mod map;
+pub mod math;
+pub mod string;
+
+/// The result of a native function, which pushes the value (if there is one) onto the stack
+pub type NativeResult = Result<Option<Value>, RuntimeError>;
+
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>) -> NativeResult;
+
+/// A function implemented in Rust, which is called just like a function defined with `begin`
+#[derive(Clone, Copy)]
+pub struct Native {
+    pub name: &'static str,
+    /// The parameters in the order they are popped off the stack, as with `begin`
+    pub params: &'static [&'static str],
+    pub func: NativeFn,
+}
+
+/// Every native function which is available by default, those using the outside world
+/// checking the interpreter's `Capabilities` when called
+pub fn standard_library() -> HashMap<String, Native> {
+    string::NATIVES
+        .iter()
+        .chain(math::NATIVES)
+        .chain(map::NATIVES)
+        .chain(json::NATIVES)
+        .chain(io::NATIVES)
+        .map(|native| (native.name.to_string(), *native))
+        .collect()
+}
@@ -0,0 +1,328 @@
+use std::borrow::Cow;
+
+use num_bigint::BigInt;
+
+use super::{Native, NativeResult};
+use crate::interpreter::{value::Value, Interpreter, RuntimeError};
+
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "len",
+        params: &["value"],
+        func: len,
+    },
+    Native {
+        name: "substr",
+        params: &["string", "start", "length"],
+        func: substr,
+    },
+    Native {
+        name: "index_of",
+        params: &["string", "needle"],
+        func: index_of,
+    },
+    Native {
+        name: "split",
+        params: &["string", "separator"],
+        func: split,
+    },
+    Native {
+        name: "join",
+        params: &["list", "separator"],
+        func: join,
+    },
+    Native {
+        name: "replace",
+        params: &["string", "from", "to"],
+        func: replace,
+    },
+    Native {
+        name: "upper",
+        params: &["string"],
+        func: upper,
+    },
+    Native {
+        name: "lower",
+        params: &["string"],
+        func: lower,
+    },
+    Native {
+        name: "trim",
+        params: &["string"],
+        func: trim,
+    },
+    Native {
+        name: "starts_with",
+        params: &["string", "prefix"],
+        func: starts_with,
+    },
+    Native {
+        name: "ends_with",
+        params: &["string", "suffix"],
+        func: ends_with,
+    },
+    Native {
+        name: "contains",
+        params: &["string", "needle"],
+        func: contains,
+    },
+    Native {
+        name: "chars",
+        params: &["string"],
+        func: chars,
+    },
+    Native {
+        name: "to_number",
+        params: &["string"],
+        func: to_number,
+    },
+    Native {
+        name: "to_string",
+        params: &["value"],
+        func: to_string,
+    },
+    Native {
+        name: "format",
+        params: &["value", "spec"],
+        func: format,
+    },
+];
+
+/// The number of characters in a string or elements in a list
+fn len(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Int(match &args[0] {
+        Value::String(string) => string.chars().count() as i64,
+        Value::Map(map) => map.len() as i64,
+        value => value.to_list()?.len() as i64,
+    })))
+}
+
+/// The characters of `string` from `start`, up to `length` of them
+fn substr(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let string = args[0].to_str()?;
+    let start = args[1].to_int()?;
+    let length = args[2].to_int()?;
+    if start < 0 || length < 0 {
+        return Err(RuntimeError::InvalidArgument {
+            function: "substr",
+            message: "start and length must not be negative".to_string(),
+        });
+    }
+
+    Ok(Some(Value::String(
+        string
+            .chars()
+            .skip(start as usize)
+            .take(length as usize)
+            .collect(),
+    )))
+}
+
+/// The character index of the first occurrence of `needle`, or -1
+fn index_of(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let string = args[0].to_str()?;
+    let needle = args[1].to_str()?;
+    Ok(Some(Value::Int(match string.find(needle) {
+        Some(index) => string[..index].chars().count() as i64,
+        None => -1,
+    })))
+}
+
+fn split(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let string = args[0].to_str()?;
+    let separator = args[1].to_str()?;
+    if separator.is_empty() {
+        return Err(RuntimeError::InvalidArgument {
+            function: "split",
+            message: "separator must not be empty, use chars instead".to_string(),
+        });
+    }
+
+    Ok(Some(Value::List(
+        string
+            .split(separator)
+            .map(|part| Value::String(part.to_string()))
+            .collect(),
+    )))
+}
+
+fn join(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let list = args[0].to_list()?;
+    let separator = args[1].to_str()?;
+    let items = list
+        .iter()
+        .map(|item| match item {
+            Value::String(string) => Cow::Borrowed(string.as_str()),
+            item => Cow::Owned(item.to_string()),
+        })
+        .collect::<Vec<_>>();
+
+    // Checked before joining so that a huge result is never allocated
+    let len = items
+        .iter()
+        .fold(0usize, |len, item| len.saturating_add(item.len()))
+        .saturating_add(
+            separator
+                .len()
+                .saturating_mul(items.len().saturating_sub(1)),
+        );
+    interpreter.limits().check_string_len(len)?;
+    Ok(Some(Value::String(items.join(separator))))
+}
+
+fn replace(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let string = args[0].to_str()?;
+    let from = args[1].to_str()?;
+    let to = args[2].to_str()?;
+    if from.is_empty() {
+        return Err(RuntimeError::InvalidArgument {
+            function: "replace",
+            message: "the string to replace must not be empty".to_string(),
+        });
+    }
+
+    // Checked before replacing so that a huge result is never allocated
+    let count = string.matches(from).count();
+    let len = (string.len() - count * from.len()).saturating_add(count.saturating_mul(to.len()));
+    interpreter.limits().check_string_len(len)?;
+    Ok(Some(Value::String(string.replace(from, to))))
+}
+
+fn upper(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::String(args[0].to_str()?.to_uppercase())))
+}
+
+fn lower(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::String(args[0].to_str()?.to_lowercase())))
+}
+
+fn trim(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::String(args[0].to_str()?.trim().to_string())))
+}
+
+fn starts_with(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Bool(
+        args[0].to_str()?.starts_with(args[1].to_str()?),
+    )))
+}
+
+fn ends_with(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Bool(
+        args[0].to_str()?.ends_with(args[1].to_str()?),
+    )))
+}
+
+fn contains(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::Bool(
+        args[0].to_str()?.contains(args[1].to_str()?),
+    )))
+}
+
+fn chars(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::List(
+        args[0]
+            .to_str()?
+            .chars()
+            .map(|char| Value::String(char.to_string()))
+            .collect(),
+    )))
+}
+
+/// Parse a string as an integer if possible, otherwise as a float
+fn to_number(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let string = args[0].to_str()?.trim();
+    if let Ok(int) = string.parse::<i64>() {
+        return Ok(Some(Value::Int(int)));
+    }
+    if let Ok(int) = string.parse::<BigInt>() {
+        return Ok(Some(Value::BigInt(int)));
+    }
+
+    match string.parse::<f64>() {
+        Ok(float) => Ok(Some(Value::Float(float))),
+        Err(_) => Err(RuntimeError::InvalidArgument {
+            function: "to_number",
+            message: format!("'{}' is not a number", string),
+        }),
+    }
+}
+
+fn to_string(_: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    Ok(Some(Value::String(args[0].to_string())))
+}
+
+/// Format a value according to `spec`, which is of the form `[<>^][0][width][.precision]`,
+/// the precision being the number of decimal places of a number or the maximum length of
+/// anything else
+fn format(interpreter: &mut Interpreter, args: Vec<Value>) -> NativeResult {
+    let spec = args[1].to_str()?;
+    let invalid = || RuntimeError::InvalidArgument {
+        function: "format",
+        message: format!("'{}' is not a valid format spec", spec),
+    };
+
+    let mut rest = spec;
+    let align = match rest.chars().next() {
+        Some(align @ ('<' | '>' | '^')) => {
+            rest = &rest[1..];
+            Some(align)
+        }
+        _ => None,
+    };
+    let zero = rest.starts_with('0');
+    if zero {
+        rest = &rest[1..];
+    }
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (
+            width,
+            Some(precision.parse::<usize>().map_err(|_| invalid())?),
+        ),
+        None => (rest, None),
+    };
+    let width = match width {
+        "" => 0,
+        width => width.parse::<usize>().map_err(|_| invalid())?,
+    };
+    // Checked up front so that a huge width cannot exhaust memory
+    interpreter.limits().check_string_len(width)?;
+    if precision.is_some_and(|precision| precision > usize::from(u16::MAX)) {
+        return Err(RuntimeError::InvalidArgument {
+            function: "format",
+            message: format!("the precision must be at most {}", u16::MAX),
+        });
+    }
+
+    let value = &args[0];
+    let is_number = value.to_number().is_ok();
+    let text = match precision {
+        Some(precision) if is_number => {
+            interpreter.limits().check_string_len(precision)?;
+            format!("{:.*}", precision, value.to_number()?)
+        }
+        Some(precision) => value.to_string().chars().take(precision).collect(),
+        None => value.to_string(),
+    };
+
+    let padding = width.saturating_sub(text.chars().count());
+    Ok(Some(Value::String(match align {
+        _ if zero && is_number && align.is_none() => {
+            let (sign, digits) = match text.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", text.as_str()),
+            };
+            format!("{}{}{}", sign, "0".repeat(padding), digits)
+        }
+        Some('<') => format!("{}{}", text, " ".repeat(padding)),
+        Some('^') => format!(
+            "{}{}{}",
+            " ".repeat(padding / 2),
+            text,
+            " ".repeat(padding - padding / 2)
+        ),
+        // Numbers are aligned right by default, like a column of figures
+        None if is_number => format!("{}{}", " ".repeat(padding), text),
+        Some(_) => format!("{}{}", " ".repeat(padding), text),
+        None => format!("{}{}", text, " ".repeat(padding)),
+    })))
+}
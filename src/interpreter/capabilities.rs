@@ -0,0 +1,42 @@
+use super::RuntimeError;
+
+/// What a program may do outside of the interpreter, everything being denied by default
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    /// Reading files, listing directories and importing modules
+    pub read_fs: bool,
+    /// Writing, appending to and removing files
+    pub write_fs: bool,
+    /// Reading command-line arguments and environment variables
+    pub env: bool,
+    /// Exiting the process with a status code
+    pub exit: bool,
+}
+
+impl Capabilities {
+    /// Every capability, as granted to scripts run from the command line
+    pub fn all() -> Self {
+        Self {
+            read_fs: true,
+            write_fs: true,
+            env: true,
+            exit: true,
+        }
+    }
+
+    /// Check that `function` may be used, `granted` being the capability it requires
+    pub fn require(
+        granted: bool,
+        function: &'static str,
+        capability: &'static str,
+    ) -> Result<(), RuntimeError> {
+        if granted {
+            Ok(())
+        } else {
+            Err(RuntimeError::CapabilityDenied {
+                function,
+                capability,
+            })
+        }
+    }
+}
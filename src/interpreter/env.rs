@@ -1,20 +1,35 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use super::{value::Value, RuntimeError, ValueResult};
 
+/// The variables of a module, shared between the module value and its functions
+pub type Globals = Rc<RefCell<HashMap<String, Value>>>;
+
+#[derive(Debug)]
+enum Scope {
+    /// The main program or a call to one of its functions, which can see its caller's variables
+    Local(HashMap<String, Value>),
+    /// A call to a function defined in a module, which can only see that module's globals
+    ModuleLocal(HashMap<String, Value>, Globals),
+    /// The top level of a module being imported
+    ModuleRoot(Globals),
+}
+
 #[derive(Debug)]
 pub struct Environment {
-    variables: Vec<HashMap<String, Value>>,
+    variables: Vec<Scope>,
     stack: Vec<Vec<Value>>,
     depth: usize,
+    total_stack_len: usize,
 }
 
 impl Default for Environment {
     fn default() -> Self {
         Self {
-            variables: vec![HashMap::new()],
+            variables: vec![Scope::Local(HashMap::new())],
             stack: vec![Vec::new()],
             depth: 0,
+            total_stack_len: 0,
         }
     }
 }
@@ -25,23 +40,88 @@ impl Environment {
     }
 
     pub fn new_scope(&mut self) {
-        self.variables.push(HashMap::new());
+        self.enter(Scope::Local(HashMap::new()));
+    }
+
+    /// Enter a call to a function defined in the module with `globals`
+    pub fn new_module_scope(&mut self, globals: Globals) {
+        self.enter(Scope::ModuleLocal(HashMap::new(), globals));
+    }
+
+    /// Enter the top level of a module, where variables are set in `globals`
+    pub fn new_module_root(&mut self, globals: Globals) {
+        self.enter(Scope::ModuleRoot(globals));
+    }
+
+    fn enter(&mut self, scope: Scope) {
+        self.variables.push(scope);
         self.stack.push(Vec::new());
         self.depth += 1;
     }
 
     pub fn exit_scope(&mut self) {
         self.variables.pop();
-        self.stack.pop();
+        if let Some(stack) = self.stack.pop() {
+            self.total_stack_len -= stack.len();
+        }
         self.depth -= 1;
     }
 
+    /// The globals of the module whose code is currently running, `None` for the main program
+    pub fn current_module(&self) -> Option<Globals> {
+        self.variables.iter().rev().find_map(|scope| match scope {
+            Scope::Local(_) => None,
+            Scope::ModuleLocal(_, globals) | Scope::ModuleRoot(globals) => Some(globals.clone()),
+        })
+    }
+
+    /// The number of nested call-frames, 0 being the global scope
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The number of values on the current call-frame's stack
+    #[inline]
+    pub fn stack_len(&self) -> usize {
+        self.stack[self.depth].len()
+    }
+
+    /// Drop the values above the first `len` of the current call-frame's stack
+    pub fn truncate_stack(&mut self, len: usize) {
+        let stack = &mut self.stack[self.depth];
+        if len < stack.len() {
+            self.total_stack_len -= stack.len() - len;
+            stack.truncate(len);
+        }
+    }
+
+    /// The number of values across every call-frame's stack
+    #[inline]
+    pub fn total_stack_len(&self) -> usize {
+        self.total_stack_len
+    }
+
     #[inline]
     pub fn get(&self, name: &str) -> ValueResult {
-        for i in (0..=self.depth).rev() {
-            match self.variables[i].get(name).map(Clone::clone) {
-                Some(value) => return Ok(value),
-                None => (),
+        for scope in self.variables.iter().rev() {
+            let value = match scope {
+                Scope::Local(variables) => variables.get(name).cloned(),
+                // Code in a module can't see past the module's globals
+                Scope::ModuleLocal(variables, globals) => match variables.get(name) {
+                    Some(value) => Some(value.clone()),
+                    None => match globals.borrow().get(name) {
+                        Some(value) => Some(value.clone()),
+                        None => break,
+                    },
+                },
+                Scope::ModuleRoot(globals) => match globals.borrow().get(name) {
+                    Some(value) => Some(value.clone()),
+                    None => break,
+                },
+            };
+            if let Some(value) = value {
+                return Ok(value);
             }
         }
 
@@ -50,39 +130,83 @@ impl Environment {
         })
     }
 
+    /// The names of every variable visible from the current scope
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for scope in self.variables.iter().rev() {
+            match scope {
+                Scope::Local(variables) => names.extend(variables.keys().cloned()),
+                Scope::ModuleLocal(variables, globals) => {
+                    names.extend(variables.keys().cloned());
+                    names.extend(globals.borrow().keys().cloned());
+                    break;
+                }
+                Scope::ModuleRoot(globals) => {
+                    names.extend(globals.borrow().keys().cloned());
+                    break;
+                }
+            }
+        }
+        names
+    }
+
     #[inline]
     pub fn set(&mut self, name: String, value: Value) {
-        self.variables
-            .get_mut(self.depth)
-            .unwrap()
-            .insert(name, value);
+        match self.variables.get_mut(self.depth).unwrap() {
+            Scope::Local(variables) | Scope::ModuleLocal(variables, _) => {
+                variables.insert(name, value);
+            }
+            Scope::ModuleRoot(globals) => {
+                globals.borrow_mut().insert(name, value);
+            }
+        }
     }
 
     #[inline]
     pub fn push(&mut self, value: Value) {
         self.stack.get_mut(self.depth).unwrap().push(value);
+        self.total_stack_len += 1;
     }
 
     #[inline]
     pub fn pop(&mut self) -> ValueResult {
-        self.stack
+        let value = self
+            .stack
             .get_mut(self.depth)
             .unwrap()
             .pop()
-            .ok_or(RuntimeError::EmptyStack)
+            .ok_or(RuntimeError::EmptyStack)?;
+        self.total_stack_len -= 1;
+        Ok(value)
     }
 
     #[inline]
     pub fn parent_pop(&mut self) -> ValueResult {
-        self.stack
+        let value = self
+            .stack
             .get_mut(self.depth - 1)
             .unwrap()
             .pop()
-            .ok_or(RuntimeError::EmptyStack)
+            .ok_or(RuntimeError::EmptyStack)?;
+        self.total_stack_len -= 1;
+        Ok(value)
     }
 
     #[inline]
     pub fn dump(&self) -> (HashMap<String, Value>, Vec<Value>) {
-        (self.variables[0].clone(), self.stack[0].clone())
+        let variables = match &self.variables[0] {
+            Scope::Local(variables) => variables.clone(),
+            _ => unreachable!("the global scope is never a module"),
+        };
+        (variables, self.stack[0].clone())
+    }
+
+    /// The variables and stack of the current call-frame
+    pub fn frame(&self) -> (HashMap<String, Value>, Vec<Value>) {
+        let variables = match &self.variables[self.depth] {
+            Scope::Local(variables) | Scope::ModuleLocal(variables, _) => variables.clone(),
+            Scope::ModuleRoot(globals) => globals.borrow().clone(),
+        };
+        (variables, self.stack[self.depth].clone())
     }
 }
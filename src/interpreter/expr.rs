@@ -1,5 +1,5 @@
 use crate::{
-    ast::{Expr, Literal},
+    ast::{Expr, Literal, StringPart},
     lexer::token_kind::TokenKind,
 };
 
@@ -8,20 +8,29 @@ use super::{value::Value, Interpreter, ValueResult};
 impl Interpreter {
     pub fn eval_expr(&mut self, expr: &Expr) -> ValueResult {
         match expr {
-            Expr::Ident(ident) => self.env.get(ident),
+            Expr::Ident(ident) => self.lookup(ident),
             Expr::Literal(literal) => Ok(Self::eval_literal(literal)),
             Expr::BinaryOp { op, lhs, rhs } => self.eval_binary_op(op, lhs, rhs),
             Expr::UnaryOp { op, expr } => self.eval_unary_op(op, expr),
+            Expr::Field { expr, field } => self.eval_expr(expr)?.field(field),
+            Expr::Index { expr, index } => {
+                let value = self.eval_expr(expr)?;
+                value.index(self.eval_expr(index)?)
+            }
+            Expr::Interpolation(parts) => self.eval_interpolation(parts),
             Expr::Pop => self.env.pop(),
         }
     }
 
     fn eval_literal(literal: &Literal) -> Value {
         match literal {
-            Literal::Int(int) => Value::Number(*int as f64),
-            Literal::Float(float) => Value::Number(*float),
+            Literal::Int(int) => Value::Int(*int),
+            Literal::BigInt(int) => Value::BigInt(int.clone()),
+            Literal::Rational(rational) => Value::Rational(rational.clone()),
+            Literal::Float(float) => Value::Float(*float),
             Literal::String(string) => Value::String(string.clone()),
             Literal::Bool(boolean) => Value::Bool(*boolean),
+            Literal::Nil => Value::Nil,
         }
     }
 
@@ -31,13 +40,21 @@ impl Interpreter {
             TokenKind::Plus
             | TokenKind::Minus
             | TokenKind::Multiply
+            | TokenKind::Power
             | TokenKind::Divide
+            | TokenKind::IntDivide
+            | TokenKind::Modulo
             | TokenKind::Less
             | TokenKind::Greater
             | TokenKind::LessEq
             | TokenKind::GreaterEq
             | TokenKind::NotEq
-            | TokenKind::Equals => self.eval_binary_op_numerical(op, lhs, rhs),
+            | TokenKind::Equals
+            | TokenKind::BitAnd
+            | TokenKind::BitOr
+            | TokenKind::BitXor
+            | TokenKind::ShiftLeft
+            | TokenKind::ShiftRight => self.eval_binary_op_numerical(op, lhs, rhs),
             TokenKind::And | TokenKind::Or => self.eval_binary_op_short_circuiting(op, lhs, rhs),
             _ => unreachable!(),
         }
@@ -46,16 +63,36 @@ impl Interpreter {
     fn eval_binary_op_numerical(&mut self, op: &TokenKind, lhs: Value, rhs: &Expr) -> ValueResult {
         let rhs = self.eval_expr(rhs)?;
         match op {
-            TokenKind::Plus => lhs.add(rhs),
+            TokenKind::Plus => {
+                if let (Value::String(lhs), Value::String(rhs)) = (&lhs, &rhs) {
+                    self.limits.check_string_len(lhs.len() + rhs.len())?;
+                }
+                lhs.add(rhs)
+            }
             TokenKind::Minus => lhs.sub(rhs),
-            TokenKind::Multiply => lhs.mul(rhs),
+            TokenKind::Multiply => {
+                // Checked before repeating so that a huge count cannot exhaust memory
+                if let Value::String(string) = &lhs {
+                    self.limits
+                        .check_string_len(string.len().saturating_mul(rhs.to_repeat_count()?))?;
+                }
+                lhs.mul(rhs)
+            }
+            TokenKind::Power => lhs.pow(rhs),
             TokenKind::Divide => lhs.div(rhs),
+            TokenKind::IntDivide => lhs.int_div(rhs),
+            TokenKind::Modulo => lhs.modulo(rhs),
             TokenKind::Less => lhs.lt(rhs),
             TokenKind::Greater => lhs.gt(rhs),
             TokenKind::LessEq => lhs.le(rhs),
             TokenKind::GreaterEq => lhs.ge(rhs),
             TokenKind::NotEq => lhs.ne(rhs),
             TokenKind::Equals => lhs.eq(rhs),
+            TokenKind::BitAnd => lhs.bit_and(rhs),
+            TokenKind::BitOr => lhs.bit_or(rhs),
+            TokenKind::BitXor => lhs.bit_xor(rhs),
+            TokenKind::ShiftLeft => lhs.shl(rhs),
+            TokenKind::ShiftRight => lhs.shr(rhs),
             _ => unreachable!(),
         }
     }
@@ -73,11 +110,28 @@ impl Interpreter {
         })
     }
 
+    fn eval_interpolation(&mut self, parts: &[StringPart]) -> ValueResult {
+        let mut string = String::new();
+        for part in parts {
+            match part {
+                StringPart::Literal(literal) => string.push_str(literal),
+                StringPart::Expr(expr) => {
+                    let value = self.eval_expr(expr)?;
+                    string.push_str(&value.to_string());
+                }
+            }
+            self.limits.check_string_len(string.len())?;
+        }
+
+        Ok(Value::String(string))
+    }
+
     fn eval_unary_op(&mut self, op: &TokenKind, expr: &Expr) -> ValueResult {
         let expr = self.eval_expr(expr)?;
         Ok(match op {
-            TokenKind::Minus => Value::Number(-expr.to_number()?),
+            TokenKind::Minus => expr.neg()?,
             TokenKind::Not => Value::Bool(!bool::from(expr)),
+            TokenKind::BitNot => expr.bit_not()?,
             _ => unreachable!(),
         })
     }
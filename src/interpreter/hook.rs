@@ -0,0 +1,54 @@
+use crate::ast::Stmt;
+
+use super::{Interpreter, StmtResult};
+
+/// Observes a running program, e.g. to debug it, and is given the interpreter to inspect the
+/// current call-frame or evaluate expressions in it
+///
+/// Returning an error from a hook stops the program as if the statement being run had failed.
+/// The hook is taken out of the interpreter while it is called, so it is not called for
+/// anything it evaluates itself
+pub trait Hook {
+    /// Called before each statement is run, including those in functions and modules
+    fn before_stmt(&mut self, _interpreter: &mut Interpreter, _stmt: &Stmt) -> StmtResult {
+        Ok(())
+    }
+
+    /// Called after each statement which runs without an error
+    fn after_stmt(&mut self, _interpreter: &mut Interpreter, _stmt: &Stmt) {}
+
+    /// Called when a function is called, after its arguments have been popped, in the
+    /// function's own call-frame unless it is a native function
+    fn enter_fn(&mut self, _interpreter: &mut Interpreter, _name: &str) -> StmtResult {
+        Ok(())
+    }
+
+    /// Called when a function which was entered returns or fails, before its call-frame is
+    /// exited
+    fn exit_fn(&mut self, _interpreter: &mut Interpreter, _name: &str) {}
+}
+
+impl Interpreter {
+    pub fn set_hook(&mut self, hook: Box<dyn Hook>) {
+        self.hook = Some(hook);
+    }
+
+    /// Remove the hook, returning it
+    pub fn take_hook(&mut self) -> Option<Box<dyn Hook>> {
+        self.hook.take()
+    }
+
+    /// Call the hook, if there is one
+    pub(super) fn with_hook<T>(
+        &mut self,
+        call: impl FnOnce(&mut dyn Hook, &mut Self) -> T,
+    ) -> Option<T> {
+        let mut hook = self.hook.take()?;
+        let result = call(hook.as_mut(), self);
+        // The hook may have replaced itself
+        if self.hook.is_none() {
+            self.hook = Some(hook);
+        }
+        Some(result)
+    }
+}
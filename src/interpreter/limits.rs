@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use super::{value::Value, RuntimeError, StmtResult};
+
+/// Resource limits placed upon a running program, `None` meaning unlimited
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// Maximum number of statements that may be executed
+    pub fuel: Option<u64>,
+    /// Maximum number of nested function calls, imports and `try` blocks, this is always bounded
+    /// because each of them recurses on the native stack. The default fits in the 2 MiB stack of
+    /// a spawned thread, even in a debug build
+    pub max_call_depth: usize,
+    /// Maximum number of values on a single call-frame's stack
+    pub max_frame_stack: Option<usize>,
+    /// Maximum number of values across every call-frame's stack
+    pub max_total_stack: Option<usize>,
+    /// Maximum length (in bytes) of any string value
+    pub max_string_len: Option<usize>,
+    /// Maximum number of elements in any list value
+    pub max_list_len: Option<usize>,
+    /// Maximum wall-clock time a single call to `Interpreter::run` may take
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            fuel: None,
+            max_call_depth: 256,
+            max_frame_stack: None,
+            max_total_stack: None,
+            max_string_len: Some(1 << 26),
+            max_list_len: Some(1 << 24),
+            timeout: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits suitable for running untrusted scripts
+    pub fn sandboxed() -> Self {
+        Self {
+            fuel: Some(10_000_000),
+            max_call_depth: 128,
+            max_frame_stack: Some(10_000),
+            max_total_stack: Some(100_000),
+            max_string_len: Some(1 << 20),
+            max_list_len: Some(1 << 16),
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+
+    pub fn check_string_len(&self, len: usize) -> StmtResult {
+        match self.max_string_len {
+            Some(limit) if len > limit => Err(RuntimeError::StringTooLong { len, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_list_len(&self, len: usize) -> StmtResult {
+        match self.max_list_len {
+            Some(limit) if len > limit => Err(RuntimeError::ListTooLong { len, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the size of a value produced by a native function
+    pub fn check_value(&self, value: &Value) -> StmtResult {
+        match value {
+            Value::String(string) => self.check_string_len(string.len()),
+            Value::List(list) => self.check_list_len(list.len()),
+            Value::Map(map) => self.check_list_len(map.len()),
+            _ => Ok(()),
+        }
+    }
+}
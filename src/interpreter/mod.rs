@@ -1,18 +1,34 @@
-use std::{cmp, fmt};
+use std::{cmp, collections::HashMap, fmt, path::PathBuf, rc::Rc, time::Instant};
 
-use crate::ast::Stmt;
+use crate::{ast::Stmt, lexer::types::Span};
 
-use self::{env::Environment, value::Value};
+use self::{
+    builtins::Native,
+    capabilities::Capabilities,
+    env::{Environment, Globals},
+    hook::Hook,
+    interrupt::InterruptHandle,
+    limits::Limits,
+    module::Module,
+    value::{Error, Value},
+};
 
+pub mod builtins;
+pub mod capabilities;
 pub mod env;
 pub mod expr;
+pub mod hook;
+pub mod interrupt;
+pub mod limits;
+pub mod module;
+pub mod prelude;
 pub mod stmt;
 pub mod value;
 
 type ValueResult = Result<Value, RuntimeError>;
 type StmtResult = Result<(), RuntimeError>;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum RuntimeError {
     WrongType {
         expected: &'static str,
@@ -21,6 +37,10 @@ pub enum RuntimeError {
     UndefinedValue {
         ident: String,
     },
+    UndefinedField {
+        field: String,
+        typ: &'static str,
+    },
     CannotPerformOnType {
         op: &'static str,
         typ: &'static str,
@@ -34,6 +54,61 @@ pub enum RuntimeError {
         typ: &'static str,
     },
     EmptyStack,
+    IndexOutOfRange {
+        index: i64,
+        len: usize,
+    },
+    InvalidArgument {
+        function: &'static str,
+        message: String,
+    },
+    IntegerOverflow {
+        op: &'static str,
+    },
+    DivisionByZero,
+    NegativeShift,
+    OutOfFuel {
+        limit: u64,
+    },
+    CallDepthExceeded {
+        limit: usize,
+    },
+    FrameStackOverflow {
+        limit: usize,
+    },
+    TotalStackOverflow {
+        limit: usize,
+    },
+    StringTooLong {
+        len: usize,
+        limit: usize,
+    },
+    ListTooLong {
+        len: usize,
+        limit: usize,
+    },
+    Import {
+        path: String,
+        message: String,
+    },
+    CapabilityDenied {
+        function: &'static str,
+        capability: &'static str,
+    },
+    Io {
+        function: &'static str,
+        message: String,
+    },
+    Exit {
+        code: i32,
+    },
+    /// An `assert` or `assert_eq` statement failing, with the span of the statement
+    AssertionFailed {
+        message: String,
+        span: Span,
+    },
+    Interrupted,
+    Thrown(Value),
 }
 
 impl fmt::Display for RuntimeError {
@@ -45,6 +120,8 @@ impl fmt::Display for RuntimeError {
                 &Self::WrongType { expected, got } =>
                     format!("Type error - expected {}, got {}", expected, got),
                 Self::UndefinedValue { ident } => format!("Value error - {} is undefined", ident),
+                Self::UndefinedField { field, typ } =>
+                    format!("Value error - {} has no field {}", typ, field),
                 &Self::CannotPerformOnType { op, typ } =>
                     format!("Type error - Cannot perform {} on {}", op, typ),
                 &Self::CannotPerformOnTypeWith { op, typ, with } => format!(
@@ -54,31 +131,243 @@ impl fmt::Display for RuntimeError {
                 &Self::CannotCompare { typ } =>
                     format!("Type error - Cannot perform comparison on {}", typ),
                 Self::EmptyStack => "Stack error - Stack is empty".to_string(),
+                Self::IndexOutOfRange { index, len } => format!(
+                    "Value error - Index {} is out of range for length {}",
+                    index, len
+                ),
+                Self::InvalidArgument { function, message } => format!(
+                    "Value error - Invalid argument to {}: {}",
+                    function, message
+                ),
+                &Self::IntegerOverflow { op } =>
+                    format!("Arithmetic error - Integer overflow in {}", op),
+                Self::DivisionByZero => "Arithmetic error - Division by zero".to_string(),
+                Self::NegativeShift => "Arithmetic error - Negative shift amount".to_string(),
+                Self::OutOfFuel { limit } =>
+                    format!("Limit error - Exceeded the limit of {} statements", limit),
+                Self::CallDepthExceeded { limit } =>
+                    format!("Limit error - Exceeded the call depth of {}", limit),
+                Self::FrameStackOverflow { limit } => format!(
+                    "Limit error - Exceeded the limit of {} values on a call-frame's stack",
+                    limit
+                ),
+                Self::TotalStackOverflow { limit } => format!(
+                    "Limit error - Exceeded the limit of {} values across all stacks",
+                    limit
+                ),
+                Self::StringTooLong { len, limit } => format!(
+                    "Limit error - String of length {} exceeds the limit of {}",
+                    len, limit
+                ),
+                Self::ListTooLong { len, limit } => format!(
+                    "Limit error - List of length {} exceeds the limit of {}",
+                    len, limit
+                ),
+                Self::Import { path, message } =>
+                    format!("Import error - Cannot import {:?}: {}", path, message),
+                Self::CapabilityDenied {
+                    function,
+                    capability,
+                } => format!(
+                    "Capability error - {} requires the {} capability",
+                    function, capability
+                ),
+                Self::Io { function, message } => format!("IO error - {}: {}", function, message),
+                Self::Exit { code } => format!("Exited with code {}", code),
+                Self::AssertionFailed { message, .. } => {
+                    format!("Assertion error - {}", message)
+                }
+                Self::Interrupted => "Interrupted".to_string(),
+                Self::Thrown(value) => format!("Uncaught exception - {}", value),
             }
         )
     }
 }
 
+impl RuntimeError {
+    /// The category of error, as seen by a `catch` block
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::WrongType { .. }
+            | Self::CannotPerformOnType { .. }
+            | Self::CannotPerformOnTypeWith { .. }
+            | Self::CannotCompare { .. } => "type",
+            Self::UndefinedValue { .. }
+            | Self::UndefinedField { .. }
+            | Self::IndexOutOfRange { .. }
+            | Self::InvalidArgument { .. } => "value",
+            Self::EmptyStack => "stack",
+            Self::IntegerOverflow { .. } | Self::DivisionByZero | Self::NegativeShift => {
+                "arithmetic"
+            }
+            Self::OutOfFuel { .. }
+            | Self::CallDepthExceeded { .. }
+            | Self::FrameStackOverflow { .. }
+            | Self::TotalStackOverflow { .. }
+            | Self::StringTooLong { .. }
+            | Self::ListTooLong { .. } => "limit",
+            Self::Import { .. } => "import",
+            Self::CapabilityDenied { .. } => "capability",
+            Self::Io { .. } => "io",
+            Self::Exit { .. } => "exit",
+            Self::AssertionFailed { .. } => "assertion",
+            Self::Interrupted => "interrupt",
+            Self::Thrown(_) => "throw",
+        }
+    }
+
+    /// Whether a `try` statement may catch this error, running out of fuel and interruption
+    /// are not catchable so that a script cannot evade them, and exiting must stop the program
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            Self::OutOfFuel { .. } | Self::Interrupted | Self::Exit { .. }
+        )
+    }
+
+    /// Convert into the value bound by a `catch` block, thrown values are bound as-is
+    pub fn into_value(self) -> Value {
+        match self {
+            Self::Thrown(value) => value,
+            err => Value::Error(Error {
+                error: Rc::new(err),
+            }),
+        }
+    }
+}
+
 pub struct Interpreter {
     env: Environment,
+    natives: HashMap<String, Native>,
+    limits: Limits,
+    fuel_used: u64,
+    interrupt: InterruptHandle,
+    deadline: Option<Instant>,
+    /// The number of function calls, imports and `try` blocks being run
+    nesting: usize,
+    /// Imported modules by canonical path
+    modules: HashMap<PathBuf, Module>,
+    /// The files currently being run, each importing the next
+    importing: Vec<PathBuf>,
+    /// The globals of the prelude, which are visible everywhere
+    prelude: Globals,
+    capabilities: Capabilities,
+    /// Command-line arguments for the program, as returned by `args`
+    args: Vec<String>,
+    hook: Option<Box<dyn Hook>>,
 }
 
 impl Default for Interpreter {
     fn default() -> Self {
-        Self {
-            env: Environment::default(),
-        }
+        Self::new()
     }
 }
 
 impl Interpreter {
+    /// Create an interpreter with the standard library and prelude loaded
     pub fn new() -> Self {
+        let mut interpreter = Self::without_prelude();
+        interpreter.load_prelude();
+        interpreter
+    }
+
+    /// Create an interpreter with the standard library's native functions but not the prelude
+    pub fn without_prelude() -> Self {
         Self {
-            env: Environment::new(),
+            env: Environment::default(),
+            natives: builtins::standard_library(),
+            limits: Limits::default(),
+            fuel_used: 0,
+            interrupt: InterruptHandle::default(),
+            deadline: None,
+            nesting: 0,
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            prelude: Globals::default(),
+            capabilities: Capabilities::default(),
+            args: Vec::new(),
+            hook: None,
         }
     }
 
+    /// Create an interpreter with the standard library and prelude loaded, the prelude being
+    /// run under `limits` too
+    pub fn with_limits(limits: Limits) -> Self {
+        let mut interpreter = Self::without_prelude();
+        interpreter.limits = limits;
+        interpreter.load_prelude();
+        interpreter
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// The number of statements executed so far, counted against `Limits::fuel`
+    pub fn fuel_used(&self) -> u64 {
+        self.fuel_used
+    }
+
+    /// Refill the fuel tank, e.g. before evaluating the next statement in the repl
+    pub fn refuel(&mut self) {
+        self.fuel_used = 0;
+    }
+
+    /// Make a native function callable, replacing any native of the same name
+    pub fn register_native(&mut self, native: Native) {
+        self.natives.insert(native.name.to_string(), native);
+    }
+
+    pub fn limits_mut(&mut self) -> &mut Limits {
+        &mut self.limits
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn capabilities_mut(&mut self) -> &mut Capabilities {
+        &mut self.capabilities
+    }
+
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    /// Get a handle which can be used to interrupt this interpreter from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Get the value of a variable, as an identifier in an expression would
+    pub fn variable(&self, name: &str) -> Option<Value> {
+        self.lookup(name).ok()
+    }
+
+    /// The names of every variable and function that can currently be used, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.env.names();
+        names.extend(self.prelude.borrow().keys().cloned());
+        names.extend(self.natives.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
     pub fn run(&mut self, stmts: &[Stmt]) -> StmtResult {
+        self.start_clock();
         for stmt in stmts {
             self.eval_stmt(stmt)?;
         }
@@ -86,12 +375,57 @@ impl Interpreter {
     }
 
     pub fn run_one(&mut self, stmt: &Stmt) -> StmtResult {
+        self.start_clock();
         self.eval_stmt(stmt)
     }
 
-    /// Why import a library when you can write 100 lines of terrible code 😎
+    /// Set the deadline according to `Limits::timeout`, relative to now
+    fn start_clock(&mut self) {
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    /// The global variables and the global stack, bottom first
+    pub fn state(&self) -> (HashMap<String, Value>, Vec<Value>) {
+        self.env.dump()
+    }
+
+    /// The variables and stack (bottom first) of the innermost call-frame
+    pub fn frame(&self) -> (HashMap<String, Value>, Vec<Value>) {
+        self.env.frame()
+    }
+
+    /// The number of nested call-frames, 0 being the main program
+    pub fn depth(&self) -> usize {
+        self.env.depth()
+    }
+
+    /// Whether the code currently running belongs to an imported module or the prelude, rather
+    /// than the main program
+    pub fn in_module(&self) -> bool {
+        self.env.current_module().is_some()
+    }
+
+    /// Clear every variable, the stack and the imported modules, keeping the natives, prelude,
+    /// limits and capabilities
+    pub fn reset(&mut self) {
+        self.env = Environment::default();
+        self.modules.clear();
+    }
+
+    /// Print the global variables and stack as a table
     pub fn print_state(&self) {
         let (variables, stack) = self.env.dump();
+        Self::print_table(&variables, &stack);
+    }
+
+    /// Print the variables and stack of the innermost call-frame as a table
+    pub fn print_frame(&self) {
+        let (variables, stack) = self.env.frame();
+        Self::print_table(&variables, &stack);
+    }
+
+    /// Why import a library when you can write 100 lines of terrible code 😎
+    fn print_table(variables: &HashMap<String, Value>, stack: &[Value]) {
         let mut variables = variables
             .iter()
             .map(|(k, v)| (k, v.to_string()))
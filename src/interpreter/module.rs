@@ -0,0 +1,146 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::parser::Parser;
+
+use super::{
+    capabilities::Capabilities, env::Globals, value::Value, Interpreter, RuntimeError, StmtResult,
+};
+
+/// Environment variable holding extra directories to search for imports
+pub const SEARCH_PATH_VAR: &str = "STACC_PATH";
+
+/// A file which has been imported, exposing its top-level functions and variables
+#[derive(Clone, Debug)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub globals: Globals,
+}
+
+impl Interpreter {
+    /// Set the path of the file being run, which its imports are resolved relative to
+    pub fn set_path(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        self.importing = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+    }
+
+    /// Forget the path set by `set_path`, so that imports are resolved relative to the
+    /// working directory again
+    pub fn clear_path(&mut self) {
+        self.importing.clear();
+    }
+
+    pub(super) fn eval_import(&mut self, path: &str, namespace: Option<&str>) -> StmtResult {
+        Capabilities::require(self.capabilities.read_fs, "import", "read_fs")?;
+        let file = self.resolve_import(path)?;
+        let namespace = match namespace {
+            Some(namespace) => namespace.to_string(),
+            None => default_namespace(path, &file)?,
+        };
+
+        if let Some(position) = self
+            .importing
+            .iter()
+            .position(|importing| *importing == file)
+        {
+            let cycle = self.importing[position..]
+                .iter()
+                .chain(Some(&file))
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(RuntimeError::Import {
+                path: path.to_string(),
+                message: format!("import cycle {}", cycle),
+            });
+        }
+
+        // Each file is only evaluated once, later imports share the same globals
+        let module = match self.modules.get(&file) {
+            Some(module) => module.clone(),
+            None => self.load_module(path, file)?,
+        };
+        self.env.set(namespace, Value::Module(module));
+        Ok(())
+    }
+
+    /// Find the file for `path`, relative to the importing file, then in each directory of
+    /// `STACC_PATH`
+    fn resolve_import(&self, path: &str) -> Result<PathBuf, RuntimeError> {
+        let base = match self.importing.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        let search_path = env::var_os(SEARCH_PATH_VAR)
+            .map(|dirs| env::split_paths(&dirs).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        std::iter::once(base)
+            .chain(search_path)
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.is_file())
+            .and_then(|file| file.canonicalize().ok())
+            .ok_or_else(|| RuntimeError::Import {
+                path: path.to_string(),
+                message: format!(
+                    "file not found relative to the importing file or in {}",
+                    SEARCH_PATH_VAR
+                ),
+            })
+    }
+
+    fn load_module(&mut self, path: &str, file: PathBuf) -> Result<Module, RuntimeError> {
+        let import_error = |message: String| RuntimeError::Import {
+            path: path.to_string(),
+            message,
+        };
+        let source = fs::read_to_string(&file).map_err(|err| import_error(err.to_string()))?;
+        let stmts = Parser::new(&source)
+            .parse()
+            .map_err(|err| import_error(err.to_string()))?;
+
+        let globals = Globals::default();
+        self.nested(|interpreter| {
+            interpreter.env.new_module_root(globals.clone());
+            interpreter.importing.push(file.clone());
+            let result = stmts
+                .iter()
+                .try_for_each(|stmt| interpreter.eval_stmt(stmt));
+            interpreter.importing.pop();
+            interpreter.env.exit_scope();
+            result
+        })?;
+
+        let module = Module {
+            name: file.file_stem().map_or_else(
+                || path.to_string(),
+                |stem| stem.to_string_lossy().to_string(),
+            ),
+            path: file.clone(),
+            globals,
+        };
+        self.modules.insert(file, module.clone());
+        Ok(module)
+    }
+}
+
+/// The name a module is bound to when it is imported without `as`, its file stem
+fn default_namespace(path: &str, file: &Path) -> Result<String, RuntimeError> {
+    let stem = file.file_stem().map(|stem| stem.to_string_lossy());
+    match stem {
+        Some(stem) if is_ident(&stem) => Ok(stem.to_string()),
+        _ => Err(RuntimeError::Import {
+            path: path.to_string(),
+            message: "file name is not a valid identifier, name it with 'as'".to_string(),
+        }),
+    }
+}
+
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(char) if char.is_ascii_alphabetic() || char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
@@ -0,0 +1,36 @@
+use crate::parser::Parser;
+
+use super::{env::Globals, Interpreter, ValueResult};
+
+/// Functions written in Stacc itself, loaded by `Interpreter::new`
+pub const PRELUDE: &str = include_str!("prelude.stacc");
+
+impl Interpreter {
+    /// Run the prelude, making its functions callable from anywhere unless they are shadowed
+    ///
+    /// The prelude runs under the interpreter's limits, except that it uses no fuel
+    pub fn load_prelude(&mut self) {
+        let stmts = Parser::new(PRELUDE)
+            .parse()
+            .expect("the prelude should parse");
+
+        // The prelude is run like a module so that its functions can call each other
+        let globals = Globals::default();
+        let fuel = self.limits.fuel.take();
+        self.env.new_module_root(globals.clone());
+        let result = stmts.iter().try_for_each(|stmt| self.eval_stmt(stmt));
+        self.env.exit_scope();
+        self.limits.fuel = fuel;
+        result.expect("the prelude should run");
+
+        self.prelude = globals;
+        self.fuel_used = 0;
+    }
+
+    /// Look up a variable, falling back to the prelude
+    pub(super) fn lookup(&self, name: &str) -> ValueResult {
+        self.env
+            .get(name)
+            .or_else(|err| self.prelude.borrow().get(name).cloned().ok_or(err))
+    }
+}
@@ -1,24 +1,58 @@
-use crate::ast::{Expr, Stmt};
+use std::{
+    rc::{Rc, Weak},
+    time::Instant,
+};
+
+use crate::{
+    ast::{Expr, Stmt, StmtKind},
+    lexer::types::Span,
+};
 
 use super::{
+    builtins::Native,
     value::{Function, Value},
-    Interpreter, StmtResult,
+    Interpreter, RuntimeError, StmtResult,
 };
 
+/// The number of statements executed between each check of the deadline
+const DEADLINE_CHECK_INTERVAL: u64 = 256;
+
 impl Interpreter {
     pub fn eval_stmt(&mut self, stmt: &Stmt) -> StmtResult {
-        match stmt {
-            Stmt::FnDef {
+        self.consume_fuel()?;
+        self.with_hook(|hook, interpreter| hook.before_stmt(interpreter, stmt))
+            .transpose()?;
+        let result = match &stmt.kind {
+            StmtKind::FnDef {
                 ident,
                 params,
                 body,
             } => self.eval_fndef(ident, params, body),
-            Stmt::Set { ident, expr } => self.eval_set(ident, expr),
-            Stmt::Push(expr) => self.eval_push(expr),
-            Stmt::Print(expr) => self.eval_print(expr),
-            Stmt::FnCall(ident) => self.eval_fncall(ident),
-            Stmt::Pop => self.eval_pop(),
+            StmtKind::Set { ident, expr } => self.eval_set(ident, expr),
+            StmtKind::Push(expr) => self.eval_push(expr),
+            StmtKind::Print(expr) => self.eval_print(expr),
+            StmtKind::FnCall(ident) => self.eval_fncall(ident),
+            StmtKind::Pop => self.eval_pop(),
+            StmtKind::Try {
+                body,
+                ident,
+                handler,
+            } => self.eval_try(body, ident, handler),
+            StmtKind::Throw(expr) => self.eval_throw(expr),
+            StmtKind::Import { path, namespace } => self.eval_import(path, namespace.as_deref()),
+            StmtKind::Assert { expr, message } => {
+                self.eval_assert(expr, message.as_ref(), stmt.span)
+            }
+            StmtKind::AssertEq {
+                left,
+                right,
+                message,
+            } => self.eval_assert_eq(left, right, message.as_ref(), stmt.span),
+        };
+        if result.is_ok() {
+            self.with_hook(|hook, interpreter| hook.after_stmt(interpreter, stmt));
         }
+        result
     }
 
     fn eval_fndef(&mut self, ident: &str, params: &[String], body: &[Stmt]) -> StmtResult {
@@ -26,6 +60,7 @@ impl Interpreter {
             ident: ident.to_string(),
             params: params.to_vec(),
             body: body.to_vec(),
+            module: self.env.current_module().as_ref().map(Rc::downgrade),
         };
         self.env.set(ident.to_string(), Value::Function(function));
         Ok(())
@@ -39,8 +74,7 @@ impl Interpreter {
 
     fn eval_push(&mut self, expr: &Expr) -> StmtResult {
         let expr = self.eval_expr(expr)?;
-        self.env.push(expr);
-        Ok(())
+        self.push(expr)
     }
 
     fn eval_print(&mut self, expr: &Expr) -> StmtResult {
@@ -50,28 +84,202 @@ impl Interpreter {
     }
 
     fn eval_fncall(&mut self, ident: &str) -> StmtResult {
-        let function = self.env.get(ident)?;
+        let function = match ident.split_once('.') {
+            // A function in an imported module
+            Some((namespace, path)) => path
+                .split('.')
+                .try_fold(self.lookup(namespace)?, |value, field| value.field(field))?,
+            // Functions defined with `begin` shadow natives
+            None => match self.lookup(ident) {
+                Ok(function) => function,
+                Err(err) => match self.natives.get(ident).copied() {
+                    Some(native) => return self.eval_native_call(native),
+                    None => return Err(err),
+                },
+            },
+        };
         let function = function.to_function()?;
 
-        self.env.new_scope();
+        self.nested(|interpreter| {
+            // Functions from a module see that module's globals rather than their caller's
+            // variables
+            match function.module.as_ref().and_then(Weak::upgrade) {
+                Some(globals) => interpreter.env.new_module_scope(globals),
+                None => interpreter.env.new_scope(),
+            }
+            // The scope must be exited even if the body fails, as the error may be caught
+            let result = interpreter.eval_fn_body(function);
+            let return_value = interpreter.env.pop();
+            interpreter.env.exit_scope();
+            result?;
+
+            match return_value {
+                Ok(value) => interpreter.push(value),
+                Err(_) => Ok(()),
+            }
+        })
+    }
+
+    fn eval_native_call(&mut self, native: Native) -> StmtResult {
+        let args = native
+            .params
+            .iter()
+            .map(|_| self.env.pop())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.with_hook(|hook, interpreter| hook.enter_fn(interpreter, native.name))
+            .transpose()?;
+        let result = (native.func)(self, args);
+        self.with_hook(|hook, interpreter| hook.exit_fn(interpreter, native.name));
+
+        match result? {
+            Some(value) => {
+                self.limits.check_value(&value)?;
+                self.push(value)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn eval_fn_body(&mut self, function: &Function) -> StmtResult {
         for param in &function.params {
             let popped = self.env.parent_pop()?;
             self.env.set(param.to_string(), popped);
         }
-        for stmt in &function.body {
-            self.eval_stmt(stmt)?;
+        self.with_hook(|hook, interpreter| hook.enter_fn(interpreter, &function.ident))
+            .transpose()?;
+        let result = function
+            .body
+            .iter()
+            .try_for_each(|stmt| self.eval_stmt(stmt));
+        self.with_hook(|hook, interpreter| hook.exit_fn(interpreter, &function.ident));
+        result
+    }
+
+    fn eval_pop(&mut self) -> StmtResult {
+        self.env.pop().map(|_| ())
+    }
+
+    fn eval_try(&mut self, body: &[Stmt], ident: &str, handler: &[Stmt]) -> StmtResult {
+        let stack_len = self.env.stack_len();
+        let result =
+            self.nested(|interpreter| body.iter().try_for_each(|stmt| interpreter.eval_stmt(stmt)));
+        match result {
+            Err(err) if err.is_catchable() => {
+                // Unwinding drops whatever the body pushed before it failed
+                self.env.truncate_stack(stack_len);
+                self.env.set(ident.to_string(), err.into_value());
+                self.nested(|interpreter| {
+                    handler
+                        .iter()
+                        .try_for_each(|stmt| interpreter.eval_stmt(stmt))
+                })
+            }
+            result => result,
         }
-        let return_value = self.env.pop();
-        self.env.exit_scope();
-        
-        if let Ok(value) = return_value {
-            self.env.push(value)
+    }
+
+    fn eval_throw(&mut self, expr: &Expr) -> StmtResult {
+        Err(match self.eval_expr(expr)? {
+            // Rethrowing a caught error raises it again as it was, rather than wrapping it
+            Value::Error(error) => {
+                Rc::try_unwrap(error.error).unwrap_or_else(|error| (*error).clone())
+            }
+            value => RuntimeError::Thrown(value),
+        })
+    }
+
+    fn eval_assert(&mut self, expr: &Expr, message: Option<&Expr>, span: Span) -> StmtResult {
+        if bool::from(self.eval_expr(expr)?) {
+            return Ok(());
+        }
+
+        let message = match message {
+            Some(message) => self.eval_expr(message)?.to_string(),
+            None => format!("{} is not true", expr),
+        };
+        Err(RuntimeError::AssertionFailed { message, span })
+    }
+
+    fn eval_assert_eq(
+        &mut self,
+        left: &Expr,
+        right: &Expr,
+        message: Option<&Expr>,
+        span: Span,
+    ) -> StmtResult {
+        let left = self.eval_expr(left)?;
+        let right = self.eval_expr(right)?;
+        if bool::from(left.eq(right.clone())?) {
+            return Ok(());
+        }
+
+        let values = format!(
+            "{} != {}",
+            left.to_quoted_string(),
+            right.to_quoted_string()
+        );
+        let message = match message {
+            Some(message) => format!("{}: {}", self.eval_expr(message)?, values),
+            None => values,
+        };
+        Err(RuntimeError::AssertionFailed { message, span })
+    }
+
+    /// Account for the execution of a statement, checking the fuel, interrupt and deadline
+    /// Run `eval` inside a function call, import or `try` block, each of which recurses on the
+    /// native stack, so together they are limited to `Limits::max_call_depth`
+    pub(super) fn nested(&mut self, eval: impl FnOnce(&mut Self) -> StmtResult) -> StmtResult {
+        if self.nesting >= self.limits.max_call_depth {
+            return Err(RuntimeError::CallDepthExceeded {
+                limit: self.limits.max_call_depth,
+            });
+        }
+
+        self.nesting += 1;
+        let result = eval(self);
+        self.nesting -= 1;
+        result
+    }
+
+    fn consume_fuel(&mut self) -> StmtResult {
+        self.fuel_used += 1;
+        if let Some(limit) = self.limits.fuel {
+            if self.fuel_used > limit {
+                return Err(RuntimeError::OutOfFuel { limit });
+            }
+        }
+
+        if self.interrupt.take() {
+            return Err(RuntimeError::Interrupted);
+        }
+
+        // Reading the clock is comparatively slow, so only do it every so often
+        if self.fuel_used.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    return Err(RuntimeError::Interrupted);
+                }
+            }
         }
 
         Ok(())
     }
 
-    fn eval_pop(&mut self) -> StmtResult {
-        self.env.pop().map(|_| ())
+    /// Push onto the current stack, respecting the stack limits
+    fn push(&mut self, value: Value) -> StmtResult {
+        if let Some(limit) = self.limits.max_frame_stack {
+            if self.env.stack_len() >= limit {
+                return Err(RuntimeError::FrameStackOverflow { limit });
+            }
+        }
+        if let Some(limit) = self.limits.max_total_stack {
+            if self.env.total_stack_len() >= limit {
+                return Err(RuntimeError::TotalStackOverflow { limit });
+            }
+        }
+
+        self.env.push(value);
+        Ok(())
     }
 }
@@ -1,15 +1,36 @@
-use std::fmt;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
 
 use crate::ast::Stmt;
 
-use super::{RuntimeError, ValueResult};
+use super::{module::Module, RuntimeError, ValueResult};
 
 #[derive(Clone, Debug)]
 pub enum Value {
     Function(Function),
     String(String),
-    Number(f64),
+    Int(i64),
+    BigInt(BigInt),
+    Rational(BigRational),
+    Float(f64),
     Bool(bool),
+    List(Vec<Value>),
+    /// String keys to values, kept in key order
+    Map(BTreeMap<String, Value>),
+    Error(Error),
+    Module(Module),
+    Nil,
 }
 
 #[derive(Clone, Debug)]
@@ -17,6 +38,23 @@ pub struct Function {
     pub ident: String,
     pub params: Vec<String>,
     pub body: Vec<Stmt>,
+    /// The globals of the module the function was defined in, if it wasn't the main program
+    pub module: Option<Weak<RefCell<HashMap<String, Value>>>>,
+}
+
+/// A `RuntimeError` caught by a `try` statement
+#[derive(Clone, Debug)]
+pub struct Error {
+    /// The error as it was raised, which throwing the value raises again
+    pub error: Rc<RuntimeError>,
+}
+
+/// The operands of an arithmetic operation, after promotion to a common type
+enum Operands {
+    Ints(i64, i64),
+    BigInts(BigInt, BigInt),
+    Rationals(BigRational, BigRational),
+    Floats(f64, f64),
 }
 
 impl Value {
@@ -24,8 +62,84 @@ impl Value {
         match self {
             Value::Function(_) => "function",
             Value::String(_) => "string",
-            Value::Number(_) => "number",
+            Value::Int(_) | Value::BigInt(_) => "integer",
+            Value::Rational(_) => "rational",
+            Value::Float(_) => "float",
             Value::Bool(_) => "boolean",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Error(_) => "error",
+            Value::Module(_) => "module",
+            Value::Nil => "nil",
+        }
+    }
+
+    pub fn field(&self, field: &str) -> ValueResult {
+        match (self, field) {
+            (Value::Error(error), "kind") => Ok(Value::String(error.error.kind().to_string())),
+            (Value::Error(error), "message") => Ok(Value::String(error.error.to_string())),
+            (Value::Map(map), field) if map.contains_key(field) => Ok(map[field].clone()),
+            (Value::Module(module), field) if module.globals.borrow().contains_key(field) => {
+                Ok(module.globals.borrow()[field].clone())
+            }
+            _ => Err(RuntimeError::UndefinedField {
+                field: field.to_string(),
+                typ: self.type_name(),
+            }),
+        }
+    }
+
+    /// Format as an element of a list or map, where strings are quoted so that the boundaries
+    /// between them are visible
+    pub(super) fn to_quoted_string(&self) -> String {
+        match self {
+            Value::String(string) => format!("{:?}", string),
+            value => value.to_string(),
+        }
+    }
+
+    /// Get the element at `index` of a list, or the character at `index` of a string,
+    /// negative indices counting back from the end
+    pub fn index(&self, index: Value) -> ValueResult {
+        let position = |len: usize| -> Result<usize, RuntimeError> {
+            let int = match index {
+                Value::Int(int) => int,
+                _ => {
+                    return Err(RuntimeError::WrongType {
+                        expected: "integer",
+                        got: index.type_name(),
+                    })
+                }
+            };
+            let position = if int < 0 { len as i64 + int } else { int };
+            if position < 0 || position >= len as i64 {
+                Err(RuntimeError::IndexOutOfRange { index: int, len })
+            } else {
+                Ok(position as usize)
+            }
+        };
+
+        match self {
+            Value::List(list) => Ok(list[position(list.len())?].clone()),
+            Value::String(string) => {
+                let position = position(string.chars().count())?;
+                Ok(Value::String(
+                    string.chars().nth(position).unwrap().to_string(),
+                ))
+            }
+            Value::Map(map) => {
+                let key = index.to_str()?;
+                map.get(key)
+                    .cloned()
+                    .ok_or_else(|| RuntimeError::UndefinedField {
+                        field: format!("{:?}", key),
+                        typ: "map",
+                    })
+            }
+            _ => Err(RuntimeError::CannotPerformOnType {
+                op: "indexing",
+                typ: self.type_name(),
+            }),
         }
     }
 
@@ -39,6 +153,16 @@ impl Value {
         }
     }
 
+    pub fn to_list(&self) -> Result<&[Value], RuntimeError> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(RuntimeError::WrongType {
+                expected: "list",
+                got: self.type_name(),
+            }),
+        }
+    }
+
     pub fn to_str(&self) -> Result<&str, RuntimeError> {
         match self {
             Value::String(string) => Ok(string),
@@ -49,9 +173,23 @@ impl Value {
         }
     }
 
+    pub fn to_int(&self) -> Result<i64, RuntimeError> {
+        match self {
+            Value::Int(int) => Ok(*int),
+            _ => Err(RuntimeError::WrongType {
+                expected: "integer",
+                got: self.type_name(),
+            }),
+        }
+    }
+
+    /// Get the value as a float, promoting integers and rationals
     pub fn to_number(&self) -> Result<f64, RuntimeError> {
         match self {
-            Value::Number(number) => Ok(*number),
+            Value::Int(int) => Ok(*int as f64),
+            Value::BigInt(int) => Ok(int.to_f64().unwrap_or(f64::NAN)),
+            Value::Rational(rational) => Ok(rational.to_f64().unwrap_or(f64::NAN)),
+            Value::Float(float) => Ok(*float),
             _ => Err(RuntimeError::WrongType {
                 expected: "number",
                 got: self.type_name(),
@@ -59,148 +197,460 @@ impl Value {
         }
     }
 
-    pub fn add(&self, other: Value) -> ValueResult {
+    /// Demote a big integer to an `Int` if it fits
+    pub fn from_bigint(int: BigInt) -> Value {
+        match int.to_i64() {
+            Some(int) => Value::Int(int),
+            None => Value::BigInt(int),
+        }
+    }
+
+    /// The position of a numeric type in the promotion order
+    fn numeric_rank(&self) -> Option<u8> {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "addition",
-                typ: "function",
-            }),
-            Value::String(string) => Ok(Value::String(string.to_string() + other.to_str()?)),
-            Value::Number(number) => Ok(Value::Number(number + other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "addition",
-                typ: "boolean",
-            }),
+            Value::Int(_) => Some(0),
+            Value::BigInt(_) => Some(1),
+            Value::Rational(_) => Some(2),
+            Value::Float(_) => Some(3),
+            _ => None,
         }
     }
 
-    pub fn sub(&self, other: Value) -> ValueResult {
+    fn to_bigint(&self) -> BigInt {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "subtraction",
-                typ: "function",
-            }),
-            Value::String(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "subtraction",
-                typ: "string",
-            }),
-            Value::Number(number) => Ok(Value::Number(number - other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "subtraction",
-                typ: "booelan",
-            }),
+            Value::Int(int) => BigInt::from(*int),
+            Value::BigInt(int) => int.clone(),
+            _ => unreachable!(),
         }
     }
 
+    fn to_rational(&self) -> BigRational {
+        match self {
+            Value::Rational(rational) => rational.clone(),
+            _ => BigRational::from_integer(self.to_bigint()),
+        }
+    }
+
+    /// Promote `self` and `other` to a common numeric type, the wider of the two in the order
+    /// integer, big integer, rational, float
+    fn operands(&self, other: &Value, op: &'static str) -> Result<Operands, RuntimeError> {
+        let lhs_rank = self
+            .numeric_rank()
+            .ok_or(RuntimeError::CannotPerformOnType {
+                op,
+                typ: self.type_name(),
+            })?;
+        let rhs_rank = other.numeric_rank().ok_or(RuntimeError::WrongType {
+            expected: "number",
+            got: other.type_name(),
+        })?;
+
+        Ok(match (self, other, lhs_rank.max(rhs_rank)) {
+            (Value::Int(lhs), Value::Int(rhs), _) => Operands::Ints(*lhs, *rhs),
+            (_, _, 1) => Operands::BigInts(self.to_bigint(), other.to_bigint()),
+            (_, _, 2) => Operands::Rationals(self.to_rational(), other.to_rational()),
+            _ => Operands::Floats(self.to_number()?, other.to_number()?),
+        })
+    }
+
+    /// Perform an operation which is closed over every numeric type, integer overflow
+    /// promotes to a big integer
+    fn arithmetic(
+        &self,
+        other: &Value,
+        op: &'static str,
+        int_op: fn(i64, i64) -> Option<i64>,
+        bigint_op: fn(BigInt, BigInt) -> BigInt,
+        rational_op: fn(BigRational, BigRational) -> BigRational,
+        float_op: fn(f64, f64) -> f64,
+    ) -> ValueResult {
+        Ok(match self.operands(other, op)? {
+            Operands::Ints(lhs, rhs) => match int_op(lhs, rhs) {
+                Some(int) => Value::Int(int),
+                None => Value::from_bigint(bigint_op(lhs.into(), rhs.into())),
+            },
+            Operands::BigInts(lhs, rhs) => Value::from_bigint(bigint_op(lhs, rhs)),
+            Operands::Rationals(lhs, rhs) => Value::Rational(rational_op(lhs, rhs)),
+            Operands::Floats(lhs, rhs) => Value::Float(float_op(lhs, rhs)),
+        })
+    }
+
+    pub fn add(&self, other: Value) -> ValueResult {
+        if let Value::String(string) = self {
+            return Ok(Value::String(string.to_string() + other.to_str()?));
+        }
+
+        self.arithmetic(
+            &other,
+            "addition",
+            i64::checked_add,
+            |lhs, rhs| lhs + rhs,
+            |lhs, rhs| lhs + rhs,
+            |lhs, rhs| lhs + rhs,
+        )
+    }
+
+    pub fn sub(&self, other: Value) -> ValueResult {
+        self.arithmetic(
+            &other,
+            "subtraction",
+            i64::checked_sub,
+            |lhs, rhs| lhs - rhs,
+            |lhs, rhs| lhs - rhs,
+            |lhs, rhs| lhs - rhs,
+        )
+    }
+
     pub fn mul(&self, other: Value) -> ValueResult {
+        if let Value::String(string) = self {
+            return Ok(Value::String(string.repeat(other.to_repeat_count()?)));
+        }
+
+        self.arithmetic(
+            &other,
+            "multiplication",
+            i64::checked_mul,
+            |lhs, rhs| lhs * rhs,
+            |lhs, rhs| lhs * rhs,
+            |lhs, rhs| lhs * rhs,
+        )
+    }
+
+    /// The number of times to repeat a string, when it is multiplied by `self`
+    pub fn to_repeat_count(&self) -> Result<usize, RuntimeError> {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotPerformOnType {
+            Value::Int(int) => Ok((*int).max(0) as usize),
+            Value::BigInt(int) if int.is_negative() => Ok(0),
+            Value::BigInt(int) => Ok(int.to_usize().unwrap_or(usize::MAX)),
+            Value::Float(float) => Ok(*float as usize),
+            _ => Err(RuntimeError::CannotPerformOnTypeWith {
                 op: "multiplication",
-                typ: "function",
+                typ: "string",
+                with: self.type_name(),
             }),
-            Value::String(string) => Ok(Value::String(match other {
-                Value::Function(_) => {
-                    return Err(RuntimeError::CannotPerformOnTypeWith {
-                        op: "multiplication",
-                        typ: "string",
-                        with: "function",
-                    })
+        }
+    }
+
+    /// True division, which produces a float unless either side is rational
+    pub fn div(&self, other: Value) -> ValueResult {
+        Ok(match self.divisor_operands(&other, "division")? {
+            Operands::Ints(lhs, rhs) => Value::Float(lhs as f64 / rhs as f64),
+            Operands::BigInts(_, _) => Value::Float(self.to_number()? / other.to_number()?),
+            Operands::Rationals(lhs, rhs) => Value::Rational(lhs / rhs),
+            Operands::Floats(lhs, rhs) => Value::Float(lhs / rhs),
+        })
+    }
+
+    /// Division rounding towards negative infinity
+    pub fn int_div(&self, other: Value) -> ValueResult {
+        Ok(match self.divisor_operands(&other, "integer division")? {
+            // Only `i64::MIN // -1` overflows
+            Operands::Ints(lhs, rhs) if lhs.checked_div(rhs).is_none() => {
+                Value::from_bigint(BigInt::from(lhs).div_floor(&rhs.into()))
+            }
+            Operands::Ints(lhs, rhs) => Value::Int(Integer::div_floor(&lhs, &rhs)),
+            Operands::BigInts(lhs, rhs) => Value::from_bigint(lhs.div_floor(&rhs)),
+            Operands::Rationals(lhs, rhs) => Value::Rational((lhs / rhs).floor()),
+            Operands::Floats(lhs, rhs) => Value::Float((lhs / rhs).floor()),
+        })
+    }
+
+    /// The remainder of `int_div`, which takes the sign of the divisor
+    pub fn modulo(&self, other: Value) -> ValueResult {
+        Ok(match self.divisor_operands(&other, "modulo")? {
+            Operands::Ints(_, -1) => Value::Int(0),
+            Operands::Ints(lhs, rhs) => Value::Int(Integer::mod_floor(&lhs, &rhs)),
+            Operands::BigInts(lhs, rhs) => Value::from_bigint(lhs.mod_floor(&rhs)),
+            Operands::Rationals(lhs, rhs) => {
+                let quotient = (&lhs / &rhs).floor();
+                Value::Rational(lhs - rhs * quotient)
+            }
+            Operands::Floats(lhs, rhs) => Value::Float(lhs - rhs * (lhs / rhs).floor()),
+        })
+    }
+
+    /// `operands`, for an operation where `other` is a divisor and so must not be zero
+    fn divisor_operands(&self, other: &Value, op: &'static str) -> Result<Operands, RuntimeError> {
+        let operands = self.operands(other, op)?;
+        let is_zero = match &operands {
+            Operands::Ints(_, rhs) => *rhs == 0,
+            Operands::BigInts(_, rhs) => rhs.is_zero(),
+            Operands::Rationals(_, rhs) => rhs.is_zero(),
+            Operands::Floats(_, rhs) => *rhs == 0.0,
+        };
+
+        if is_zero {
+            Err(RuntimeError::DivisionByZero)
+        } else {
+            Ok(operands)
+        }
+    }
+
+    /// Exponentiation, which is exact for integer and rational bases with integer exponents
+    pub fn pow(&self, other: Value) -> ValueResult {
+        let operands = self.operands(&other, "exponentiation")?;
+        let exponent = match &other {
+            Value::Int(int) => Some(*int),
+            _ => None,
+        };
+
+        Ok(match (operands, exponent) {
+            (Operands::Ints(base, _), Some(exponent)) if exponent >= 0 => {
+                match u32::try_from(exponent)
+                    .ok()
+                    .and_then(|exp| base.checked_pow(exp))
+                {
+                    Some(int) => Value::Int(int),
+                    None => Value::from_bigint(bigint_pow(BigInt::from(base), exponent as u64)?),
                 }
-                Value::String(_) => {
-                    return Err(RuntimeError::CannotPerformOnTypeWith {
-                        op: "multiplication",
-                        typ: "string",
-                        with: "string",
-                    })
+            }
+            (Operands::BigInts(base, _), Some(exponent)) if exponent >= 0 => {
+                Value::from_bigint(bigint_pow(base, exponent as u64)?)
+            }
+            (Operands::Rationals(base, _), Some(exponent)) => {
+                if base.is_zero() && exponent < 0 {
+                    return Err(RuntimeError::DivisionByZero);
                 }
-                Value::Number(number) => string.repeat(number as usize),
-                Value::Bool(_) => {
-                    return Err(RuntimeError::CannotPerformOnTypeWith {
-                        op: "multiplication",
-                        typ: "string",
-                        with: "boolean",
-                    })
+                let numerator = bigint_pow(base.numer().clone(), exponent.unsigned_abs())?;
+                let denominator = bigint_pow(base.denom().clone(), exponent.unsigned_abs())?;
+                if exponent < 0 {
+                    Value::Rational(BigRational::new(denominator, numerator))
+                } else {
+                    Value::Rational(BigRational::new(numerator, denominator))
                 }
-            })),
-            Value::Number(number) => Ok(Value::Number(number * other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "multiplication",
-                typ: "boolean",
+            }
+            _ => {
+                let (base, exponent) = (self.to_number()?, other.to_number()?);
+                // A negative power of zero divides by zero, which is an error rather than `inf`
+                if base == 0.0 && exponent < 0.0 {
+                    return Err(RuntimeError::DivisionByZero);
+                }
+                Value::Float(base.powf(exponent))
+            }
+        })
+    }
+
+    /// Perform a bitwise operation, which is only defined for integers
+    fn bitwise(
+        &self,
+        other: &Value,
+        op: &'static str,
+        int_op: fn(i64, i64) -> i64,
+        bigint_op: fn(BigInt, BigInt) -> BigInt,
+    ) -> ValueResult {
+        match self.operands(other, op)? {
+            Operands::Ints(lhs, rhs) => Ok(Value::Int(int_op(lhs, rhs))),
+            Operands::BigInts(lhs, rhs) => Ok(Value::from_bigint(bigint_op(lhs, rhs))),
+            _ => Err(RuntimeError::CannotPerformOnType {
+                op,
+                typ: self.non_integral_type_name(other),
             }),
         }
     }
 
-    pub fn div(&self, other: Value) -> ValueResult {
+    /// The type name of whichever of `self` and `other` is not an integer
+    fn non_integral_type_name(&self, other: &Value) -> &'static str {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "division",
-                typ: "function",
-            }),
-            Value::String(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "division",
-                typ: "string",
-            }),
-            Value::Number(number) => Ok(Value::Number(number / other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotPerformOnType {
-                op: "division",
-                typ: "boolean",
-            }),
+            Value::Int(_) | Value::BigInt(_) => other.type_name(),
+            _ => self.type_name(),
         }
     }
 
-    pub fn lt(&self, other: Value) -> ValueResult {
+    pub fn bit_and(&self, other: Value) -> ValueResult {
+        self.bitwise(
+            &other,
+            "bitwise and",
+            |lhs, rhs| lhs & rhs,
+            |lhs, rhs| lhs & rhs,
+        )
+    }
+
+    pub fn bit_or(&self, other: Value) -> ValueResult {
+        self.bitwise(
+            &other,
+            "bitwise or",
+            |lhs, rhs| lhs | rhs,
+            |lhs, rhs| lhs | rhs,
+        )
+    }
+
+    pub fn bit_xor(&self, other: Value) -> ValueResult {
+        self.bitwise(
+            &other,
+            "bitwise xor",
+            |lhs, rhs| lhs ^ rhs,
+            |lhs, rhs| lhs ^ rhs,
+        )
+    }
+
+    pub fn bit_not(&self) -> ValueResult {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string.as_str() < other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number < other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+            Value::Int(int) => Ok(Value::Int(!int)),
+            Value::BigInt(int) => Ok(Value::from_bigint(!int)),
+            _ => Err(RuntimeError::CannotPerformOnType {
+                op: "bitwise not",
+                typ: self.type_name(),
+            }),
         }
     }
 
-    pub fn gt(&self, other: Value) -> ValueResult {
-        match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string.as_str() > other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number > other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+    /// Shift left, promoting to a big integer rather than losing bits
+    pub fn shl(&self, other: Value) -> ValueResult {
+        let (lhs, shift) = self.shift_operands(&other, "left shift")?;
+        if !lhs.is_zero() && lhs.bits().saturating_add(shift) > MAX_POW_BITS {
+            return Err(RuntimeError::IntegerOverflow { op: "left shift" });
         }
+        Ok(Value::from_bigint(lhs << shift))
     }
 
-    pub fn le(&self, other: Value) -> ValueResult {
-        match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string.as_str() <= other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number <= other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+    /// Arithmetic shift right, which rounds towards negative infinity
+    pub fn shr(&self, other: Value) -> ValueResult {
+        let (lhs, shift) = self.shift_operands(&other, "right shift")?;
+        Ok(Value::from_bigint(lhs >> shift))
+    }
+
+    fn shift_operands(
+        &self,
+        other: &Value,
+        op: &'static str,
+    ) -> Result<(BigInt, u64), RuntimeError> {
+        match self.operands(other, op)? {
+            Operands::Ints(_, _) | Operands::BigInts(_, _) => (),
+            _ => {
+                return Err(RuntimeError::CannotPerformOnType {
+                    op,
+                    typ: self.non_integral_type_name(other),
+                })
+            }
+        }
+
+        let shift = other.to_bigint();
+        if shift.is_negative() {
+            return Err(RuntimeError::NegativeShift);
         }
+        Ok((self.to_bigint(), shift.to_u64().unwrap_or(u64::MAX)))
     }
 
-    pub fn ge(&self, other: Value) -> ValueResult {
+    pub fn neg(&self) -> ValueResult {
         match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string.as_str() >= other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number >= other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+            Value::Int(int) => Ok(match int.checked_neg() {
+                Some(int) => Value::Int(int),
+                None => Value::from_bigint(-BigInt::from(*int)),
+            }),
+            Value::BigInt(int) => Ok(Value::from_bigint(-int)),
+            Value::Rational(rational) => Ok(Value::Rational(-rational)),
+            _ => Ok(Value::Float(-self.to_number()?)),
         }
     }
 
+    /// Compare `self` with `other`, only strings and numbers are ordered
+    fn compare(&self, other: &Value) -> Result<Option<Ordering>, RuntimeError> {
+        if let Value::String(string) = self {
+            return Ok(Some(string.as_str().cmp(other.to_str()?)));
+        }
+        if self.numeric_rank().is_none() {
+            return Err(RuntimeError::CannotCompare {
+                typ: self.type_name(),
+            });
+        }
+
+        Ok(match self.operands(other, "comparison")? {
+            Operands::Ints(lhs, rhs) => Some(lhs.cmp(&rhs)),
+            Operands::BigInts(lhs, rhs) => Some(lhs.cmp(&rhs)),
+            Operands::Rationals(lhs, rhs) => Some(lhs.cmp(&rhs)),
+            Operands::Floats(lhs, rhs) => lhs.partial_cmp(&rhs),
+        })
+    }
+
+    pub fn lt(&self, other: Value) -> ValueResult {
+        Ok(Value::Bool(self.compare(&other)? == Some(Ordering::Less)))
+    }
+
+    pub fn gt(&self, other: Value) -> ValueResult {
+        Ok(Value::Bool(
+            self.compare(&other)? == Some(Ordering::Greater),
+        ))
+    }
+
+    pub fn le(&self, other: Value) -> ValueResult {
+        Ok(Value::Bool(matches!(
+            self.compare(&other)?,
+            Some(Ordering::Less | Ordering::Equal)
+        )))
+    }
+
+    pub fn ge(&self, other: Value) -> ValueResult {
+        Ok(Value::Bool(matches!(
+            self.compare(&other)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        )))
+    }
+
     pub fn eq(&self, other: Value) -> ValueResult {
-        match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string == other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number == other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+        // Anything can be checked for being nil
+        if let (Value::Nil, _) | (_, Value::Nil) = (self, &other) {
+            return Ok(Value::Bool(matches!(
+                (self, other),
+                (Value::Nil, Value::Nil)
+            )));
         }
+        // Booleans, lists and maps aren't ordered, but are equal when their contents are
+        match (self, &other) {
+            (Value::Bool(lhs), Value::Bool(rhs)) => return Ok(Value::Bool(lhs == rhs)),
+            (Value::List(lhs), Value::List(rhs)) => {
+                return Ok(Value::Bool(
+                    lhs.len() == rhs.len() && all_eq(lhs.iter().zip(rhs))?,
+                ))
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                return Ok(Value::Bool(
+                    lhs.len() == rhs.len()
+                        && lhs.keys().eq(rhs.keys())
+                        && all_eq(lhs.values().zip(rhs.values()))?,
+                ))
+            }
+            _ => (),
+        }
+        Ok(Value::Bool(self.compare(&other)? == Some(Ordering::Equal)))
     }
 
     pub fn ne(&self, other: Value) -> ValueResult {
-        match self {
-            Value::Function(_) => Err(RuntimeError::CannotCompare { typ: "function" }),
-            Value::String(string) => Ok(Value::Bool(string != other.to_str()?)),
-            Value::Number(number) => Ok(Value::Bool(*number != other.to_number()?)),
-            Value::Bool(_) => Err(RuntimeError::CannotCompare { typ: "boolean" }),
+        Ok(Value::Bool(!bool::from(self.eq(other)?)))
+    }
+}
+
+/// Whether every pair of values is equal
+fn all_eq<'a>(pairs: impl Iterator<Item = (&'a Value, &'a Value)>) -> Result<bool, RuntimeError> {
+    for (lhs, rhs) in pairs {
+        if !bool::from(lhs.eq(rhs.clone())?) {
+            return Ok(false);
         }
     }
+    Ok(true)
+}
+
+/// The largest number of bits `bigint_pow` will produce, so that `10 ** 10 ** 10` is an error
+/// rather than exhausting memory
+const MAX_POW_BITS: u64 = 1 << 26;
+
+fn bigint_pow(base: BigInt, exponent: u64) -> Result<BigInt, RuntimeError> {
+    let overflow = RuntimeError::IntegerOverflow {
+        op: "exponentiation",
+    };
+    if base.bits() > 1 && exponent.saturating_mul(base.bits()) > MAX_POW_BITS {
+        return Err(overflow);
+    }
+
+    // Bases of 0, 1 and -1 can't grow, so only whether the exponent is zero, odd or even matters
+    let exponent = if base.bits() <= 1 {
+        match exponent {
+            0 => 0,
+            exponent => ((exponent - 1) % 2 + 1) as u32,
+        }
+    } else {
+        u32::try_from(exponent).map_err(|_| overflow)?
+    };
+    Ok(base.pow(exponent))
 }
 
 impl From<Value> for bool {
@@ -208,8 +658,15 @@ impl From<Value> for bool {
         match value {
             Value::Function(function) => !function.body.is_empty(),
             Value::String(string) => !string.is_empty(),
-            Value::Number(number) => number == 0.0,
+            Value::Int(int) => int != 0,
+            Value::BigInt(int) => !int.is_zero(),
+            Value::Rational(rational) => !rational.is_zero(),
+            Value::Float(float) => float != 0.0,
             Value::Bool(boolean) => boolean,
+            Value::List(list) => !list.is_empty(),
+            Value::Map(map) => !map.is_empty(),
+            Value::Error(_) | Value::Module(_) => true,
+            Value::Nil => false,
         }
     }
 }
@@ -226,8 +683,31 @@ impl fmt::Display for Value {
                     function.params.join(", ")
                 ),
                 Value::String(string) => string.to_string(),
-                Value::Number(number) => number.to_string(),
+                Value::Int(int) => int.to_string(),
+                Value::BigInt(int) => int.to_string(),
+                // Integral rationals keep their suffix so that they are distinct from integers
+                Value::Rational(rational) if rational.is_integer() => format!("{}r", rational),
+                Value::Rational(rational) => rational.to_string(),
+                // Debug formatting always includes the decimal point, so `1.0` is distinct from `1`
+                Value::Float(float) => format!("{:?}", float),
                 Value::Bool(boolean) => boolean.to_string(),
+                Value::List(list) => format!(
+                    "[{}]",
+                    list.iter()
+                        .map(Value::to_quoted_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Value::Map(map) => format!(
+                    "{{{}}}",
+                    map.iter()
+                        .map(|(key, value)| format!("{:?}: {}", key, value.to_quoted_string()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Value::Error(error) => error.error.to_string(),
+                Value::Module(module) => format!("<module {}>", module.name),
+                Value::Nil => "nil".to_string(),
             }
         )
     }
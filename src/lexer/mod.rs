@@ -4,20 +4,29 @@ pub mod types;
 use token_kind::*;
 use types::*;
 
+use std::ops::Range;
+
 use logos::Logos;
 
 pub struct Lexer<'input> {
     input: &'input str,
     generated: logos::SpannedIter<'input, LogosToken>,
     eof: bool,
+    offset: usize,
 }
 
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
+        Self::new_in(input, 0..input.len())
+    }
+
+    /// Lex only `range` of `input`, with spans still relative to the whole of `input`
+    pub fn new_in(input: &'input str, range: Range<usize>) -> Self {
         Self {
-            input: input,
-            generated: LogosToken::lexer(input).spanned(),
+            input: &input[..range.end],
+            generated: LogosToken::lexer(&input[range.clone()]).spanned(),
             eof: false,
+            offset: range.start,
         }
     }
 }
@@ -29,14 +38,15 @@ impl<'input> Iterator for Lexer<'input> {
         match self.generated.next() {
             Some((token, span)) => Some(Token {
                 kind: TokenKind::from(token),
-                span: span.into(),
+                span: (span.start + self.offset..span.end + self.offset).into(),
             }),
             None if self.eof => None,
             None => {
                 self.eof = true;
                 Some(Token {
                     kind: TokenKind::Eof,
-                    span: (self.input.len() - 1..self.input.len() - 1).into(),
+                    span: (self.input.len().saturating_sub(1)..self.input.len().saturating_sub(1))
+                        .into(),
                 })
             }
         }
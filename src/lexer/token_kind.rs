@@ -1,6 +1,30 @@
 use logos::Logos;
 use std::fmt;
 
+/// The words matched by the `#[token]`s of `LogosToken`, e.g. for completion in the repl
+pub const KEYWORDS: &[&str] = &[
+    "pop",
+    "print",
+    "push",
+    "set",
+    "call",
+    "try",
+    "catch",
+    "throw",
+    "import",
+    "as",
+    "assert",
+    "assert_eq",
+    "begin",
+    "end",
+    "true",
+    "false",
+    "nil",
+    "and",
+    "not",
+    "or",
+];
+
 #[derive(Logos, Clone, Debug, PartialEq)]
 pub enum LogosToken {
     #[token("pop")]
@@ -18,18 +42,52 @@ pub enum LogosToken {
     #[token("call")]
     Call,
 
+    #[token("try")]
+    Try,
+
+    #[token("catch")]
+    Catch,
+
+    #[token("throw")]
+    Throw,
+
+    #[token("import")]
+    Import,
+
+    #[token("as")]
+    As,
+
+    #[token("assert")]
+    Assert,
+
+    #[token("assert_eq")]
+    AssertEq,
+
     #[regex(r#"([A-Za-z]|_)([A-Za-z]|_|\d)*"#)]
     Ident,
 
-    #[regex("[0-9]+", priority = 2)]
+    // Any alphanumeric characters after a radix prefix are lexed, so that the parser can point
+    // out invalid digits. Plain integers also match `FloatLit`, so they need a higher priority
+    #[regex(r"\d[\d_]*|0[xXoObB][0-9A-Za-z_]*", priority = 2)]
     IntLit,
 
-    #[regex(r#"((\d+(\.\d+)?)|(\.\d+))([Ee](\+|-)?\d+)?"#, priority = 1)]
+    #[regex(
+        r"((\d[\d_]*(\.\d[\d_]*)?)|(\.\d[\d_]*))([Ee][+-]?\d[\d_]*)?",
+        priority = 1
+    )]
     FloatLit,
 
-    #[regex(r#""((\\"|\\\\)|[^\\"])*""#)]
+    #[regex(r"((\d[\d_]*(\.\d[\d_]*)?)|(\.\d[\d_]*))r")]
+    RationalLit,
+
+    #[regex(r#""([^"\\]|\\(.|\n))*""#)]
+    #[regex(r#""""([^"]|"[^"]|""[^"])*""""#)]
     StringLit,
 
+    #[regex(r#"r"[^"]*""#)]
+    #[regex(r#"r"""([^"]|"[^"]|""[^"])*""""#)]
+    RawStringLit,
+
     #[token("begin")]
     Begin,
 
@@ -45,6 +103,9 @@ pub enum LogosToken {
     #[token("false")]
     False,
 
+    #[token("nil")]
+    Nil,
+
     #[token("and")]
     And,
 
@@ -60,6 +121,12 @@ pub enum LogosToken {
     #[token(":")]
     Colon,
 
+    #[token(".")]
+    Dot,
+
+    #[token(",")]
+    Comma,
+
     #[token("[")]
     LeftBracket,
 
@@ -81,9 +148,18 @@ pub enum LogosToken {
     #[token("*")]
     Multiply,
 
+    #[token("**")]
+    Power,
+
     #[token("/")]
     Divide,
 
+    #[token("//")]
+    IntDivide,
+
+    #[token("%")]
+    Modulo,
+
     #[token("<")]
     Less,
 
@@ -102,6 +178,24 @@ pub enum LogosToken {
     #[token("==")]
     Equals,
 
+    #[token("&")]
+    BitAnd,
+
+    #[token("|")]
+    BitOr,
+
+    #[token("^")]
+    BitXor,
+
+    #[token("~")]
+    BitNot,
+
+    #[token("<<")]
+    ShiftLeft,
+
+    #[token(">>")]
+    ShiftRight,
+
     #[error]
     #[regex(r"[ \t\f]+", logos::skip)]
     Error,
@@ -114,20 +208,34 @@ pub enum TokenKind {
     Push,
     Set,
     Call,
+    Try,
+    Catch,
+    Throw,
+    Import,
+    As,
+    Assert,
+    AssertEq,
     Ident,
     IntLit,
     FloatLit,
+    RationalLit,
     StringLit,
+    RawStringLit,
     Begin,
     End,
     Comment,
+    /// Spaces and tabs, which the lexer skips but the concrete syntax tree keeps
+    Whitespace,
     True,
     False,
+    Nil,
     And,
     Not,
     Or,
     Newline,
     Colon,
+    Dot,
+    Comma,
     LeftBracket,
     RightBracket,
     LeftParen,
@@ -135,13 +243,22 @@ pub enum TokenKind {
     Plus,
     Minus,
     Multiply,
+    Power,
     Divide,
+    IntDivide,
+    Modulo,
     Less,
     Greater,
     LessEq,
     GreaterEq,
     NotEq,
     Equals,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
     Error,
     Eof,
 }
@@ -157,20 +274,33 @@ impl fmt::Display for TokenKind {
                 Self::Push => "push",
                 Self::Set => "set",
                 Self::Call => "call",
+                Self::Try => "try",
+                Self::Catch => "'catch'",
+                Self::Throw => "throw",
+                Self::Import => "import",
+                Self::As => "'as'",
+                Self::Assert => "assert",
+                Self::AssertEq => "assert_eq",
                 Self::Ident => "identifier",
                 Self::IntLit => "integer literal",
                 Self::FloatLit => "float literal",
+                Self::RationalLit => "rational literal",
                 Self::StringLit => "string literal",
+                Self::RawStringLit => "raw string literal",
                 Self::Begin => "begin",
                 Self::End => "'end'",
                 Self::Comment => "comment literal",
+                Self::Whitespace => "whitespace",
                 Self::True => "true",
                 Self::False => "false",
+                Self::Nil => "nil",
                 Self::And => "and",
                 Self::Not => "not",
                 Self::Or => "or",
                 Self::Newline => "newline",
                 Self::Colon => "colon",
+                Self::Dot => ".",
+                Self::Comma => "comma",
                 Self::LeftBracket => "[",
                 Self::RightBracket => "]",
                 Self::LeftParen => "(",
@@ -178,13 +308,22 @@ impl fmt::Display for TokenKind {
                 Self::Plus => "+",
                 Self::Minus => "-",
                 Self::Multiply => "*",
+                Self::Power => "**",
                 Self::Divide => "/",
+                Self::IntDivide => "//",
+                Self::Modulo => "%",
                 Self::Less => "<",
                 Self::Greater => ">",
                 Self::LessEq => "<=",
                 Self::GreaterEq => ">=",
                 Self::NotEq => "!=",
                 Self::Equals => "==",
+                Self::BitAnd => "&",
+                Self::BitOr => "|",
+                Self::BitXor => "^",
+                Self::BitNot => "~",
+                Self::ShiftLeft => "<<",
+                Self::ShiftRight => ">>",
                 Self::Error => "error",
                 Self::Eof => "EOF",
             }
@@ -200,20 +339,32 @@ impl From<LogosToken> for TokenKind {
             LogosToken::Push => Self::Push,
             LogosToken::Set => Self::Set,
             LogosToken::Call => Self::Call,
+            LogosToken::Try => Self::Try,
+            LogosToken::Catch => Self::Catch,
+            LogosToken::Throw => Self::Throw,
+            LogosToken::Import => Self::Import,
+            LogosToken::As => Self::As,
+            LogosToken::Assert => Self::Assert,
+            LogosToken::AssertEq => Self::AssertEq,
             LogosToken::Ident => Self::Ident,
             LogosToken::IntLit => Self::IntLit,
             LogosToken::FloatLit => Self::FloatLit,
+            LogosToken::RationalLit => Self::RationalLit,
             LogosToken::StringLit => Self::StringLit,
+            LogosToken::RawStringLit => Self::RawStringLit,
             LogosToken::Begin => Self::Begin,
             LogosToken::End => Self::End,
             LogosToken::Comment => Self::Comment,
             LogosToken::True => Self::True,
             LogosToken::False => Self::False,
+            LogosToken::Nil => Self::Nil,
             LogosToken::And => Self::And,
             LogosToken::Not => Self::Not,
             LogosToken::Or => Self::Or,
             LogosToken::Newline => Self::Newline,
             LogosToken::Colon => Self::Colon,
+            LogosToken::Dot => Self::Dot,
+            LogosToken::Comma => Self::Comma,
             LogosToken::LeftBracket => Self::LeftBracket,
             LogosToken::RightBracket => Self::RightBracket,
             LogosToken::LeftParen => Self::LeftParen,
@@ -221,13 +372,22 @@ impl From<LogosToken> for TokenKind {
             LogosToken::Plus => Self::Plus,
             LogosToken::Minus => Self::Minus,
             LogosToken::Multiply => Self::Multiply,
+            LogosToken::Power => Self::Power,
             LogosToken::Divide => Self::Divide,
+            LogosToken::IntDivide => Self::IntDivide,
+            LogosToken::Modulo => Self::Modulo,
             LogosToken::Less => Self::Less,
             LogosToken::Greater => Self::Greater,
             LogosToken::LessEq => Self::LessEq,
             LogosToken::GreaterEq => Self::GreaterEq,
             LogosToken::NotEq => Self::NotEq,
             LogosToken::Equals => Self::Equals,
+            LogosToken::BitAnd => Self::BitAnd,
+            LogosToken::BitOr => Self::BitOr,
+            LogosToken::BitXor => Self::BitXor,
+            LogosToken::BitNot => Self::BitNot,
+            LogosToken::ShiftLeft => Self::ShiftLeft,
+            LogosToken::ShiftRight => Self::ShiftRight,
             LogosToken::Error => Self::Error,
         }
     }
@@ -18,28 +18,28 @@ impl Token {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
 }
 
 impl Span {
-    /// Return the line number (0 based) and column number (0 based)
+    /// Return the line number (0 based) and column number (0 based, in characters)
     /// of the token (relative to the input string)
     pub fn get_line_and_column(&self, input: &str) -> (usize, usize) {
-        let start = self.start as usize;
         let mut line = 0;
         let mut column = 0;
-        for (index, byte) in input.bytes().enumerate() {
-            if index == start {
+        for (index, char) in input.char_indices() {
+            if index >= self.start {
                 break;
             }
-            if byte == b'\n' {
+            if char == '\n' {
                 line += 1;
                 column = 0;
+            } else {
+                column += 1;
             }
-            column += 1;
         }
         (line, column)
     }
@@ -51,7 +51,7 @@ impl Span {
 
 impl From<Span> for Range<usize> {
     fn from(span: Span) -> Self {
-        span.start as usize..span.end as usize
+        span.start..span.end
     }
 }
 
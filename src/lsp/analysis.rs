@@ -0,0 +1,248 @@
+use lsp_types::{Position, Range};
+use stacc::{
+    ast::{Stmt, StmtKind},
+    lexer::{
+        token_kind::TokenKind,
+        types::{Span, Token},
+        Lexer,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefinitionKind {
+    Function {
+        params: Vec<String>,
+    },
+    Variable,
+    /// A parameter of the function whose span is the definition's scope
+    Param,
+    /// The error bound by a `catch`
+    Caught,
+    Module,
+}
+
+/// Something a name is bound to by a statement
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub name: String,
+    pub kind: DefinitionKind,
+    /// The name where it is bound
+    pub name_span: Span,
+    /// The whole statement binding it
+    pub span: Span,
+    /// The function definition it is inside, `None` at the top level
+    pub scope: Option<Span>,
+}
+
+/// Every definition in `stmts`, in the order they appear
+pub fn definitions(source: &str, stmts: &[Stmt]) -> Vec<Definition> {
+    let mut definitions = Vec::new();
+    collect(source, stmts, None, &mut definitions);
+    definitions
+}
+
+fn collect(source: &str, stmts: &[Stmt], scope: Option<Span>, definitions: &mut Vec<Definition>) {
+    for stmt in stmts {
+        let mut define = |name: &str, kind, name_span: Option<Span>, scope| {
+            definitions.push(Definition {
+                name: name.to_string(),
+                kind,
+                name_span: name_span.unwrap_or(stmt.span),
+                span: stmt.span,
+                scope,
+            })
+        };
+        match &stmt.kind {
+            StmtKind::FnDef {
+                ident,
+                params,
+                body,
+            } => {
+                let tokens = tokens(source, stmt.span);
+                let kind = DefinitionKind::Function {
+                    params: params.clone(),
+                };
+                define(ident, kind, ident_after(&tokens, TokenKind::Begin), scope);
+
+                // The parameters are the identifiers after the colon
+                let param_spans = tokens
+                    .iter()
+                    .skip_while(|token| token.kind != TokenKind::Colon)
+                    .take_while(|token| token.kind != TokenKind::Newline)
+                    .filter(|token| token.kind == TokenKind::Ident)
+                    .map(|token| token.span);
+                for (param, span) in params.iter().zip(param_spans) {
+                    define(param, DefinitionKind::Param, Some(span), Some(stmt.span));
+                }
+                collect(source, body, Some(stmt.span), definitions);
+            }
+            StmtKind::Set { ident, .. } => {
+                let span = ident_after(&tokens(source, stmt.span), TokenKind::Set);
+                define(ident, DefinitionKind::Variable, span, scope);
+            }
+            StmtKind::Try {
+                body,
+                ident,
+                handler,
+            } => {
+                collect(source, body, scope, definitions);
+                let span = catch_ident(&tokens(source, stmt.span));
+                definitions.push(Definition {
+                    name: ident.to_string(),
+                    kind: DefinitionKind::Caught,
+                    name_span: span.unwrap_or(stmt.span),
+                    span: stmt.span,
+                    scope,
+                });
+                collect(source, handler, scope, definitions);
+            }
+            StmtKind::Import { path, namespace } => {
+                let tokens = tokens(source, stmt.span);
+                let name = match namespace {
+                    Some(namespace) => namespace.clone(),
+                    None => match std::path::Path::new(path).file_stem() {
+                        Some(stem) => stem.to_string_lossy().to_string(),
+                        None => continue,
+                    },
+                };
+                let span = ident_after(&tokens, TokenKind::As).or_else(|| {
+                    tokens
+                        .iter()
+                        .find(|token| {
+                            matches!(token.kind, TokenKind::StringLit | TokenKind::RawStringLit)
+                        })
+                        .map(|token| token.span)
+                });
+                define(&name, DefinitionKind::Module, span, scope);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The tokens in `span` of `source`, without comments
+pub fn tokens(source: &str, span: Span) -> Vec<Token> {
+    Lexer::new_in(source, span.into())
+        .filter(|token| !matches!(token.kind, TokenKind::Comment | TokenKind::Eof))
+        .collect()
+}
+
+/// The span of the identifier straight after the first `keyword`
+fn ident_after(tokens: &[Token], keyword: TokenKind) -> Option<Span> {
+    tokens
+        .windows(2)
+        .find(|pair| pair[0].kind == keyword && pair[1].kind == TokenKind::Ident)
+        .map(|pair| pair[1].span)
+}
+
+/// The span of the identifier after the `catch` of the `try` statement made of `tokens`,
+/// skipping those of any blocks inside it
+fn catch_ident(tokens: &[Token]) -> Option<Span> {
+    let mut depth = 0usize;
+    for pair in tokens.windows(2) {
+        match pair[0].kind {
+            TokenKind::Begin | TokenKind::Try => depth += 1,
+            TokenKind::End => depth = depth.saturating_sub(1),
+            TokenKind::Catch if depth == 1 && pair[1].kind == TokenKind::Ident => {
+                return Some(pair[1].span)
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The definition `name` refers to at `offset`, preferring functions if it is `call`ed and
+/// anything else otherwise, then those in the innermost function containing `offset`, then the
+/// last one bound before it
+pub fn resolve<'a>(
+    definitions: &'a [Definition],
+    name: &str,
+    offset: usize,
+    call: bool,
+) -> Option<&'a Definition> {
+    let contains = |span: Span| span.start <= offset && offset <= span.end;
+    let named = definitions
+        .iter()
+        .filter(|definition| definition.name == name)
+        .filter(|definition| definition.scope.is_none_or(contains));
+    let is_function = |definition: &&Definition| {
+        matches!(definition.kind, DefinitionKind::Function { .. }) == call
+    };
+    let preferred = named.clone().any(|definition| is_function(&definition));
+    let visible = named.filter(|definition| !preferred || is_function(definition));
+    let innermost = visible
+        .clone()
+        .filter_map(|definition| definition.scope)
+        .min_by_key(|scope| scope.end - scope.start);
+
+    let candidates = visible
+        .filter(|definition| definition.scope == innermost)
+        .collect::<Vec<_>>();
+    // A variable is only set once its value has been evaluated, which may use its old value
+    let bound = |definition: &Definition| {
+        definition.name_span.start <= offset
+            && (definition.kind != DefinitionKind::Variable
+                || offset <= definition.name_span.end
+                || definition.span.end <= offset)
+    };
+    candidates
+        .iter()
+        .rev()
+        .find(|definition| bound(definition))
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+/// The identifier token at `offset` and whether it follows `call`, unless it is a field or
+/// module member after a `.`
+pub fn ident_at(source: &str, offset: usize) -> Option<(Token, bool)> {
+    let tokens = tokens(source, (0..source.len()).into());
+    let index = tokens.iter().position(|token| {
+        token.kind == TokenKind::Ident && token.span.start <= offset && offset <= token.span.end
+    })?;
+    let prev = index.checked_sub(1).map(|index| tokens[index].kind);
+    if prev == Some(TokenKind::Dot) {
+        return None;
+    }
+    Some((tokens[index], prev == Some(TokenKind::Call)))
+}
+
+/// The position of a byte offset, with the column in UTF-16 code units as LSP expects
+pub fn position(source: &str, offset: usize) -> Position {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+pub fn range(source: &str, span: Span) -> Range {
+    Range {
+        start: position(source, span.start),
+        end: position(source, span.end),
+    }
+}
+
+/// The byte offset of a position, clamped to the end of its line
+pub fn offset(source: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match source[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return source.len(),
+        }
+    }
+
+    let line = source[line_start..].split('\n').next().unwrap_or_default();
+    let mut units = 0;
+    for (index, char) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + index;
+        }
+        units += char.len_utf16();
+    }
+    line_start + line.len()
+}
@@ -0,0 +1,491 @@
+mod analysis;
+
+use std::{collections::HashMap, error::Error};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Request as _,
+    },
+    CompletionItem, CompletionItemKind, CompletionResponse, Diagnostic, DiagnosticSeverity,
+    DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Hover, HoverContents,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Url,
+};
+use stacc::{
+    cst::format::format,
+    interpreter::{builtins::standard_library, prelude::PRELUDE},
+    lexer::{token_kind::KEYWORDS, types::Span},
+    parser::{ParseError, Parser},
+};
+
+use analysis::{Definition, DefinitionKind};
+
+/// Serve the Language Server Protocol over stdin and stdout until the client exits
+pub fn lsp() -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(Default::default()),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    Server {
+        connection,
+        documents: HashMap::new(),
+        library: library(),
+    }
+    .main_loop()?;
+    // The connection has to be dropped first, for the thread writing to stdout to finish
+    io_threads.join()?;
+    Ok(())
+}
+
+/// An open document
+struct Document {
+    text: String,
+    /// The definitions as of the last time the text parsed, so that names can still be
+    /// completed while it is being edited
+    definitions: Vec<Definition>,
+    /// Whether the definitions are for the current text
+    parsed: bool,
+}
+
+/// A function which is available without being defined, from the standard library or prelude
+struct LibraryFunction {
+    name: String,
+    params: Vec<String>,
+    docs: String,
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, Document>,
+    library: Vec<LibraryFunction>,
+}
+
+impl Server {
+    fn main_loop(mut self) -> Result<(), Box<dyn Error + Sync + Send>> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.request(request);
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => self.notification(notification)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => handle::<GotoDefinition>(request, |params| {
+                let params = params.text_document_position_params;
+                let location = self.definition(&params.text_document.uri, params.position)?;
+                Ok(location.map(GotoDefinitionResponse::Scalar))
+            }),
+            HoverRequest::METHOD => handle::<HoverRequest>(request, |params| {
+                let params = params.text_document_position_params;
+                self.hover(&params.text_document.uri, params.position)
+            }),
+            DocumentSymbolRequest::METHOD => handle::<DocumentSymbolRequest>(request, |params| {
+                let symbols = self.symbols(&params.text_document.uri)?;
+                Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+            }),
+            Completion::METHOD => handle::<Completion>(request, |params| {
+                let params = params.text_document_position;
+                let items = self.completion(&params.text_document.uri, params.position)?;
+                Ok(Some(CompletionResponse::Array(items)))
+            }),
+            Formatting::METHOD => {
+                handle::<Formatting>(request, |params| self.formatting(&params.text_document.uri))
+            }
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("Unknown method {}", method),
+            )),
+        };
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    fn notification(
+        &mut self,
+        notification: Notification,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Some(params) = params::<DidOpenTextDocument>(notification) {
+                    self.update(params.text_document.uri, params.text_document.text)?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Some(params) = params::<DidChangeTextDocument>(notification) {
+                    // With full syncing, the last change is the whole text
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.update(params.text_document.uri, change.text)?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Some(params) = params::<DidCloseTextDocument>(notification) {
+                    self.documents.remove(&params.text_document.uri);
+                    self.publish(params.text_document.uri, Vec::new())?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Replace the text of a document, parsing it and publishing any error
+    fn update(&mut self, uri: Url, text: String) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let document = self.documents.entry(uri.clone()).or_insert(Document {
+            text: String::new(),
+            definitions: Vec::new(),
+            parsed: false,
+        });
+        let diagnostics = match Parser::new(&text).parse() {
+            Ok(stmts) => {
+                document.definitions = analysis::definitions(&text, &stmts);
+                document.parsed = true;
+                Vec::new()
+            }
+            Err(err) => {
+                document.parsed = false;
+                vec![diagnostic(&text, &err)]
+            }
+        };
+        document.text = text;
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(
+        &self,
+        uri: Url,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+
+    fn document(&self, uri: &Url) -> Result<&Document, String> {
+        self.documents
+            .get(uri)
+            .ok_or_else(|| format!("{} isn't open", uri))
+    }
+
+    /// The definition referred to by the identifier at `position`, if the document parses
+    fn resolve(&self, uri: &Url, position: Position) -> Result<Option<&Definition>, String> {
+        let document = self.document(uri)?;
+        if !document.parsed {
+            return Ok(None);
+        }
+        let offset = analysis::offset(&document.text, position);
+        Ok(
+            analysis::ident_at(&document.text, offset).and_then(|(token, call)| {
+                let name = token.text(&document.text);
+                analysis::resolve(&document.definitions, name, offset, call)
+            }),
+        )
+    }
+
+    fn definition(&self, uri: &Url, position: Position) -> Result<Option<Location>, String> {
+        let document = self.document(uri)?;
+        Ok(self.resolve(uri, position)?.map(|definition| Location {
+            uri: uri.clone(),
+            range: analysis::range(&document.text, definition.name_span),
+        }))
+    }
+
+    fn hover(&self, uri: &Url, position: Position) -> Result<Option<Hover>, String> {
+        let document = self.document(uri)?;
+        let text = document.text.as_str();
+        let value = match self.resolve(uri, position)? {
+            Some(definition) => {
+                let code = match &definition.kind {
+                    DefinitionKind::Function { params } => signature(&definition.name, params),
+                    DefinitionKind::Param => signature_line(text, definition.span),
+                    DefinitionKind::Caught => format!("catch {}", definition.name),
+                    _ => text[definition.span].trim().to_string(),
+                };
+                markdown(&code, &docs(text, definition.span))
+            }
+            // Functions which aren't defined in the document are from the library
+            None => {
+                let offset = analysis::offset(text, position);
+                let function = analysis::ident_at(text, offset).and_then(|(token, _)| {
+                    let name = token.text(text);
+                    self.library.iter().find(|function| function.name == name)
+                });
+                match function {
+                    Some(function) => {
+                        markdown(&signature(&function.name, &function.params), &function.docs)
+                    }
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        }))
+    }
+
+    fn symbols(&self, uri: &Url) -> Result<Vec<DocumentSymbol>, String> {
+        let document = self.document(uri)?;
+        Ok(symbols(&document.text, &document.definitions, None))
+    }
+
+    fn completion(&self, uri: &Url, position: Position) -> Result<Vec<CompletionItem>, String> {
+        let document = self.document(uri)?;
+        let offset = analysis::offset(&document.text, position);
+        let mut items = KEYWORDS
+            .iter()
+            .map(|keyword| CompletionItem {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        // Only the definitions which are in scope, unless they are out of date
+        let contains = |span: Span| span.start <= offset && offset <= span.end;
+        let definitions = document
+            .definitions
+            .iter()
+            .filter(|definition| !document.parsed || definition.scope.is_none_or(contains));
+        for definition in definitions {
+            let (kind, detail) = match &definition.kind {
+                DefinitionKind::Function { params } => (
+                    CompletionItemKind::FUNCTION,
+                    signature(&definition.name, params),
+                ),
+                DefinitionKind::Module => (
+                    CompletionItemKind::MODULE,
+                    document.text.as_str()[definition.span].trim().to_string(),
+                ),
+                _ => (CompletionItemKind::VARIABLE, String::new()),
+            };
+            items.push(CompletionItem {
+                label: definition.name.clone(),
+                kind: Some(kind),
+                detail: Some(detail).filter(|detail| !detail.is_empty()),
+                ..Default::default()
+            });
+        }
+        items.extend(self.library.iter().map(|function| CompletionItem {
+            label: function.name.clone(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some(signature(&function.name, &function.params)),
+            ..Default::default()
+        }));
+
+        let mut seen = std::collections::HashSet::new();
+        items.retain(|item| seen.insert(item.label.clone()));
+        Ok(items)
+    }
+
+    fn formatting(&self, uri: &Url) -> Result<Option<Vec<TextEdit>>, String> {
+        let document = self.document(uri)?;
+        // Invalid documents are left alone, their error being a diagnostic already
+        let formatted = match format(&document.text) {
+            Ok(formatted) => formatted,
+            Err(_) => return Ok(None),
+        };
+        if formatted == document.text {
+            return Ok(Some(Vec::new()));
+        }
+        Ok(Some(vec![TextEdit {
+            range: Range {
+                start: Position::new(0, 0),
+                end: analysis::position(&document.text, document.text.len()),
+            },
+            new_text: formatted,
+        }]))
+    }
+}
+
+/// Run `handler` with the parameters of `request`, which is an `R`, returning its result as
+/// JSON
+fn handle<R: lsp_types::request::Request>(
+    request: Request,
+    handler: impl FnOnce(R::Params) -> Result<R::Result, String>,
+) -> Result<serde_json::Value, (ErrorCode, String)> {
+    let (_, params) = request
+        .extract::<R::Params>(R::METHOD)
+        .map_err(|err| (ErrorCode::InvalidParams, format!("{:?}", err)))?;
+    let result = handler(params).map_err(|err| (ErrorCode::InvalidRequest, err))?;
+    serde_json::to_value(result).map_err(|err| (ErrorCode::InternalError, err.to_string()))
+}
+
+/// The parameters of `notification`, which is an `N`, or `None` if they are invalid, which is
+/// logged to stderr rather than stopping the server, as there is no response to report it in
+fn params<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> Option<N::Params> {
+    match notification.extract::<N::Params>(N::METHOD) {
+        Ok(params) => Some(params),
+        Err(err) => {
+            eprintln!("Invalid {} notification: {:?}", N::METHOD, err);
+            None
+        }
+    }
+}
+
+/// A diagnostic for a parse error, covering the span it is about
+fn diagnostic(text: &str, err: &ParseError) -> Diagnostic {
+    let (span, message) = match err {
+        ParseError::Syntax { span, message, .. } => (*span, message.clone()),
+        // Running out of input is an error at the end of it
+        ParseError::UnexpectedEof => ((text.len()..text.len()).into(), err.to_string()),
+    };
+
+    Diagnostic {
+        range: analysis::range(text, span),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("stacc".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// The symbols for the definitions in the function spanning `scope`, or the top level if it is
+/// `None`, with those of each function inside it, keeping only the first of each name
+#[allow(deprecated)]
+fn symbols(text: &str, definitions: &[Definition], scope: Option<Span>) -> Vec<DocumentSymbol> {
+    let mut found: Vec<DocumentSymbol> = Vec::new();
+    for definition in definitions {
+        let seen = found.iter().any(|symbol| symbol.name == definition.name);
+        if definition.scope != scope || seen {
+            continue;
+        }
+        let (kind, detail, children) = match &definition.kind {
+            DefinitionKind::Function { params } => (
+                SymbolKind::FUNCTION,
+                Some(signature(&definition.name, params)),
+                Some(symbols(text, definitions, Some(definition.span))),
+            ),
+            DefinitionKind::Module => (SymbolKind::MODULE, None, None),
+            _ => (SymbolKind::VARIABLE, None, None),
+        };
+        found.push(DocumentSymbol {
+            name: definition.name.clone(),
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: analysis::range(text, definition.span),
+            selection_range: analysis::range(text, definition.name_span),
+            children,
+        });
+    }
+    found
+}
+
+fn signature(name: &str, params: &[String]) -> String {
+    if params.is_empty() {
+        format!("begin {}:", name)
+    } else {
+        format!("begin {}: {}", name, params.join(" "))
+    }
+}
+
+/// The first line of the statement spanning `span`
+fn signature_line(text: &str, span: Span) -> String {
+    text[span]
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// The text of the comments on the lines right before the statement spanning `span`
+fn docs(text: &str, span: Span) -> String {
+    let mut lines = text[..span.start]
+        .lines()
+        .rev()
+        // The start of the statement's own line
+        .skip(usize::from(
+            !text[..span.start].ends_with('\n') && span.start > 0,
+        ))
+        .map(str::trim)
+        .take_while(|line| line.starts_with('{') && line.ends_with('}'))
+        .map(|line| line[1..line.len() - 1].trim())
+        .collect::<Vec<_>>();
+    lines.reverse();
+    lines.join("\n")
+}
+
+fn markdown(code: &str, docs: &str) -> String {
+    let mut value = format!("```stacc\n{}\n```", code);
+    if !docs.is_empty() {
+        value.push_str("\n\n");
+        value.push_str(docs);
+    }
+    value
+}
+
+/// The native functions and those of the prelude, sorted by name
+fn library() -> Vec<LibraryFunction> {
+    let mut functions = standard_library()
+        .into_values()
+        .map(|native| LibraryFunction {
+            name: native.name.to_string(),
+            params: native
+                .params
+                .iter()
+                .map(|param| param.to_string())
+                .collect(),
+            docs: "Native function".to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let stmts = Parser::new(PRELUDE)
+        .parse()
+        .expect("the prelude should parse");
+    for definition in analysis::definitions(PRELUDE, &stmts) {
+        if let (DefinitionKind::Function { params }, None) = (&definition.kind, definition.scope) {
+            let docs = docs(PRELUDE, definition.span);
+            functions.push(LibraryFunction {
+                name: definition.name.clone(),
+                params: params.clone(),
+                docs: if docs.is_empty() {
+                    "Prelude function".to_string()
+                } else {
+                    docs
+                },
+            });
+        }
+    }
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    functions
+}
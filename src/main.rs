@@ -1,10 +1,113 @@
+mod debugger;
+mod formatter;
+mod lsp;
+mod profile;
+mod repl;
+mod test_runner;
+mod trace;
+
 use std::{
-    env, fmt, fs,
-    io::{self, Write},
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
     process,
 };
 
-use stacc::{interpreter::Interpreter, parser::Parser};
+use clap::{error::ErrorKind, Args, CommandFactory, Parser as _, Subcommand};
+use stacc::{
+    interpreter::{capabilities::Capabilities, Interpreter, RuntimeError},
+    parser::Parser,
+};
+
+/// Exit code for a script which fails to parse
+const EXIT_PARSE_ERROR: i32 = 65;
+/// Exit code for a script which fails while running
+const EXIT_RUNTIME_ERROR: i32 = 70;
+/// Exit code for a script which can't be read
+const EXIT_IO_ERROR: i32 = 74;
+
+/// A dynamically typed, interpreted, stack-based language
+#[derive(clap::Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Run CODE instead of a file, any positional arguments are passed to it
+    #[arg(short, long, value_name = "CODE")]
+    eval: Option<String>,
+
+    /// Start without the prelude
+    #[arg(long, global = true)]
+    no_prelude: bool,
+
+    /// The script to run, or - to read it from stdin
+    file: Option<String>,
+
+    /// Arguments for the script, returned by `args`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a script
+    Run {
+        /// Print each statement and the stack after it runs to stderr, as text or JSON lines
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "text"
+        )]
+        trace: Option<trace::Format>,
+
+        /// Print the calls and time spent in each function and the statements run on each
+        /// line to stderr
+        #[arg(long, conflicts_with = "trace")]
+        profile: bool,
+
+        /// Write the time spent in each stack of calls to FILE, in the folded format of
+        /// flamegraph tools
+        #[arg(long, value_name = "FILE", conflicts_with = "trace")]
+        profile_folded: Option<PathBuf>,
+
+        #[command(flatten)]
+        script: Script,
+    },
+    /// Run a script in the debugger, pausing before its first statement
+    Debug(Script),
+    /// Start an interactive session, the default when no file is given
+    Repl,
+    /// Format files and directories of `.stacc` files in place, or stdin to stdout if given -
+    Fmt {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Don't write the files, only list those which aren't formatted, failing if there are
+        /// any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Serve the Language Server Protocol on stdin and stdout, for editors
+    Lsp,
+    /// Run the `test_*` functions in the given files and directories of `.stacc` files
+    Test {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct Script {
+    /// The script to run, or - to read it from stdin
+    file: String,
+
+    /// Arguments for the script, returned by `args`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
 
 fn unwrap<T, E: fmt::Display + fmt::Debug>(result: Result<T, E>) -> T {
     if let Err(err) = result {
@@ -16,37 +119,152 @@ fn unwrap<T, E: fmt::Display + fmt::Debug>(result: Result<T, E>) -> T {
 }
 
 fn main() {
-    let filename = env::args().nth(1);
-    if filename.is_none() {
-        repl();
-        process::exit(1);
+    let cli = Cli::parse();
+    // A script argument which happens to be the name of a subcommand is ambiguous
+    if cli.command.is_some() && (cli.file.is_some() || cli.eval.is_some()) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "a subcommand can't follow a file or --eval, use `stacc run FILE [ARGS]...`",
+            )
+            .exit();
     }
 
-    let filename = filename.unwrap();
-    let contents = unwrap(fs::read_to_string(filename));
-    let stmts = unwrap(Parser::new(&contents).parse());
+    let no_prelude = cli.no_prelude;
+    let new_interpreter = move || {
+        let mut interpreter = if no_prelude {
+            Interpreter::without_prelude()
+        } else {
+            Interpreter::new()
+        };
+        interpreter.set_capabilities(Capabilities::all());
+        interpreter
+    };
+    let mut interpreter = new_interpreter();
+
+    if let Some(code) = cli.eval {
+        // Without a file, every positional argument is for the code
+        interpreter.set_args(cli.file.into_iter().chain(cli.args).collect());
+        process::exit(run(&mut interpreter, &format!("{}\n", code)));
+    }
+
+    match cli.command {
+        Some(Command::Run {
+            trace,
+            profile,
+            profile_folded,
+            script,
+        }) => {
+            let contents = read_script(&mut interpreter, script);
+            if profile || profile_folded.is_some() {
+                process::exit(profile::profile(
+                    interpreter,
+                    &contents,
+                    profile,
+                    profile_folded,
+                ));
+            }
+            if let Some(format) = trace {
+                interpreter.set_hook(Box::new(trace::Tracer::new(&contents, format)));
+            }
+            process::exit(run(&mut interpreter, &contents));
+        }
+        Some(Command::Debug(script)) => {
+            let contents = read_script(&mut interpreter, script);
+            debugger::debug(interpreter, &contents);
+        }
+        Some(Command::Repl) => repl::repl(interpreter),
+        Some(Command::Fmt { paths, check }) => process::exit(formatter::fmt(&paths, check)),
+        Some(Command::Lsp) => unwrap(lsp::lsp()),
+        Some(Command::Test { paths }) => process::exit(test_runner::test(&paths, new_interpreter)),
+        None => match cli.file {
+            Some(file) => run_script(
+                interpreter,
+                Script {
+                    file,
+                    args: cli.args,
+                },
+            ),
+            None => repl::repl(interpreter),
+        },
+    }
+}
+
+fn run_script(mut interpreter: Interpreter, script: Script) {
+    let contents = read_script(&mut interpreter, script);
+    process::exit(run(&mut interpreter, &contents));
+}
+
+/// Read the source of `script`, setting the interpreter up to run it
+fn read_script(interpreter: &mut Interpreter, script: Script) -> String {
+    let contents = if script.file == "-" {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents).map(|_| contents)
+    } else {
+        interpreter.set_path(&script.file);
+        fs::read_to_string(&script.file)
+    };
+    let contents = contents.unwrap_or_else(|err| {
+        eprintln!("{}: {}", script.file, err);
+        process::exit(EXIT_IO_ERROR);
+    });
 
-    let mut interpreter = Interpreter::new();
-    unwrap(interpreter.run(&stmts))
+    interpreter.set_args(script.args);
+    contents
 }
 
-fn repl() {
-    let mut interpreter = Interpreter::new();
-    loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+/// Run `code`, printing any error, and return the code to exit with
+fn run(interpreter: &mut Interpreter, code: &str) -> i32 {
+    let stmts = match Parser::new(code).parse() {
+        Ok(stmts) => stmts,
+        Err(err) => {
+            eprintln!("{}", err);
+            return EXIT_PARSE_ERROR;
+        }
+    };
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        input.push('\n');
+    match interpreter.run(&stmts) {
+        Ok(()) => 0,
+        Err(RuntimeError::Exit { code }) => code,
+        Err(err) => {
+            eprintln!("{}", err);
+            match err {
+                RuntimeError::Io { .. } => EXIT_IO_ERROR,
+                _ => EXIT_RUNTIME_ERROR,
+            }
+        }
+    }
+}
+
+/// Add `path` if it is a file, or every `.stacc` file in it if it is a directory, in order of
+/// their paths, skipping hidden files and directories
+fn find_scripts(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        // Check that the file exists, so that a mistyped path isn't silently ignored
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
 
-        let stmt = Parser::new(&input).parse_stmt();
-        match stmt {
-            Ok(stmt) => match interpreter.run_one(&stmt) {
-                Ok(_) => interpreter.print_state(),
-                Err(err) => eprintln!("{}", err),
-            },
-            Err(err) => eprintln!("{}", err),
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if entry.is_dir() {
+            find_scripts(&entry, files)?;
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "stacc")
+        {
+            files.push(entry);
         }
     }
+    Ok(())
 }
@@ -1,11 +1,14 @@
 use crate::{
     ast::{Expr, Literal},
-    lexer::token_kind::TokenKind,
+    lexer::{token_kind::TokenKind, types::Token},
 };
 
-use super::Parser;
+use num_bigint::BigInt;
+use num_rational::BigRational;
 
-type ExprResult = Result<Expr, String>;
+use super::{ParseError, Parser};
+
+type ExprResult = Result<Expr, ParseError>;
 
 /// A trait that allows you to get the binding power of the operator `self`
 trait Operator {
@@ -16,7 +19,7 @@ trait Operator {
 impl Operator for TokenKind {
     fn prefix_binding_power(&self) -> Option<((), u8)> {
         Some(match self {
-            TokenKind::Minus => ((), 51),
+            TokenKind::Minus | TokenKind::BitNot => ((), 51),
             TokenKind::Not => ((), 101),
             _ => return None,
         })
@@ -30,8 +33,16 @@ impl Operator for TokenKind {
             TokenKind::Less | TokenKind::Greater | TokenKind::LessEq | TokenKind::GreaterEq => {
                 (7, 8)
             }
-            TokenKind::Plus | TokenKind::Minus => (9, 10),
-            TokenKind::Multiply | TokenKind::Divide => (11, 12),
+            TokenKind::BitOr => (9, 10),
+            TokenKind::BitXor => (11, 12),
+            TokenKind::BitAnd => (13, 14),
+            TokenKind::ShiftLeft | TokenKind::ShiftRight => (15, 16),
+            TokenKind::Plus | TokenKind::Minus => (17, 18),
+            TokenKind::Multiply | TokenKind::Divide | TokenKind::IntDivide | TokenKind::Modulo => {
+                (19, 20)
+            }
+            // Right associative, and binds tighter than prefix `-` so `-2 ** 2` is -4
+            TokenKind::Power => (53, 52),
             _ => return None,
         })
     }
@@ -39,17 +50,26 @@ impl Operator for TokenKind {
 
 impl Parser<'_> {
     fn parse_expr(&mut self, binding_power: u8) -> ExprResult {
+        self.nested(|parser| parser.parse_nested_expr(binding_power))
+    }
+
+    fn parse_nested_expr(&mut self, binding_power: u8) -> ExprResult {
         let mut lhs = match self.peek() {
             TokenKind::Ident => self.parse_ident()?,
             TokenKind::Pop => self.parse_pop_expr()?,
             lit @ TokenKind::IntLit
             | lit @ TokenKind::FloatLit
+            | lit @ TokenKind::RationalLit
             | lit @ TokenKind::StringLit
+            | lit @ TokenKind::RawStringLit
             | lit @ TokenKind::True
-            | lit @ TokenKind::False => self.parse_lit(lit)?,
+            | lit @ TokenKind::False
+            | lit @ TokenKind::Nil => self.parse_lit(lit)?,
             TokenKind::LeftParen => self.parse_grouping()?,
-            op @ TokenKind::Minus | op @ TokenKind::Not => self.parse_prefix_op(op)?,
-            TokenKind::Eof => return Err("Parse error: Unexpected EOF".to_string()),
+            op @ TokenKind::Minus | op @ TokenKind::Not | op @ TokenKind::BitNot => {
+                self.parse_prefix_op(op)?
+            }
+            TokenKind::Eof => return Err(ParseError::UnexpectedEof),
             _ => {
                 let token = self.next_token().unwrap();
                 return Err(self.fmt_error(
@@ -60,11 +80,24 @@ impl Parser<'_> {
         };
 
         loop {
+            if self.at(TokenKind::Dot) {
+                lhs = self.parse_field(lhs)?;
+                continue;
+            }
+
+            if self.at(TokenKind::LeftBracket) {
+                lhs = self.parse_index(lhs)?;
+                continue;
+            }
+
             let op = match self.peek() {
                 op @ TokenKind::Plus
                 | op @ TokenKind::Minus
                 | op @ TokenKind::Multiply
+                | op @ TokenKind::Power
                 | op @ TokenKind::Divide
+                | op @ TokenKind::IntDivide
+                | op @ TokenKind::Modulo
                 | op @ TokenKind::And
                 | op @ TokenKind::Or
                 | op @ TokenKind::Less
@@ -73,8 +106,17 @@ impl Parser<'_> {
                 | op @ TokenKind::LessEq
                 | op @ TokenKind::GreaterEq
                 | op @ TokenKind::NotEq
-                | op @ TokenKind::Equals => op,
-                TokenKind::Eof | TokenKind::RightParen | TokenKind::Newline => break,
+                | op @ TokenKind::Equals
+                | op @ TokenKind::BitAnd
+                | op @ TokenKind::BitOr
+                | op @ TokenKind::BitXor
+                | op @ TokenKind::ShiftLeft
+                | op @ TokenKind::ShiftRight => op,
+                TokenKind::Eof
+                | TokenKind::RightParen
+                | TokenKind::RightBracket
+                | TokenKind::Comma
+                | TokenKind::Newline => break,
                 _ => {
                     let token = self.next_token().unwrap();
                     return Err(self.fmt_error(
@@ -115,6 +157,33 @@ impl Parser<'_> {
         Ok(Expr::Ident(text.to_string()))
     }
 
+    fn parse_field(&mut self, expr: Expr) -> ExprResult {
+        self.next_token().unwrap();
+        let token = self.next_token().unwrap();
+        if token.kind != TokenKind::Ident {
+            return Err(self.fmt_error(
+                token.span,
+                format!("Expected field name, got {}", token.kind),
+            ));
+        }
+
+        Ok(Expr::Field {
+            expr: Box::new(expr),
+            field: self.text(token).to_string(),
+        })
+    }
+
+    fn parse_index(&mut self, expr: Expr) -> ExprResult {
+        self.next_token().unwrap();
+        let index = self.expr()?;
+        self.consume(TokenKind::RightBracket)?;
+
+        Ok(Expr::Index {
+            expr: Box::new(expr),
+            index: Box::new(index),
+        })
+    }
+
     fn parse_pop_expr(&mut self) -> ExprResult {
         self.next_token().unwrap();
         Ok(Expr::Pop)
@@ -125,29 +194,97 @@ impl Parser<'_> {
         let text = self.text(token);
 
         Ok(Expr::Literal(match lit {
-            TokenKind::IntLit => Literal::Int(text.parse::<i64>().map_err(|_| {
-                self.fmt_error(
-                    token.span,
-                    format!("'{}' is not a valid integer literal", text),
-                )
-            })?),
-            TokenKind::FloatLit => Literal::Float(text.parse::<f64>().map_err(|_| {
-                self.fmt_error(
-                    token.span,
-                    format!("'{}' is not a valid float literal", text),
-                )
-            })?),
-            TokenKind::StringLit => Literal::String(text[1..(text.len() - 1)].to_string()),
+            TokenKind::IntLit => self.parse_int(token)?,
+            TokenKind::FloatLit => self.parse_float(token)?,
+            TokenKind::RationalLit => Literal::Rational(Self::parse_rational(text)),
+            TokenKind::StringLit | TokenKind::RawStringLit => return self.parse_string(token),
             TokenKind::True => Literal::Bool(true),
             TokenKind::False => Literal::Bool(false),
+            TokenKind::Nil => Literal::Nil,
             _ => unreachable!(),
         }))
     }
 
+    /// Parse a decimal, hex (`0x`), octal (`0o`) or binary (`0b`) integer literal which may
+    /// contain `_` separators, those too large for an `i64` becoming big integers
+    fn parse_int(&self, token: Token) -> Result<Literal, ParseError> {
+        let text = self.text(token);
+        let (prefix, radix, name) = match text.get(..2) {
+            Some("0x") | Some("0X") => (2, 16, "hex"),
+            Some("0o") | Some("0O") => (2, 8, "octal"),
+            Some("0b") | Some("0B") => (2, 2, "binary"),
+            _ => (0, 10, "decimal"),
+        };
+
+        let mut digits = String::with_capacity(text.len());
+        for (index, char) in text.char_indices().skip(prefix) {
+            if char == '_' {
+                continue;
+            }
+            if !char.is_digit(radix) {
+                let start = token.span.start + index;
+                return Err(self.fmt_error(
+                    (start..start + char.len_utf8()).into(),
+                    format!(
+                        "'{}' is not a valid digit in {} literal '{}'",
+                        char, name, text
+                    ),
+                ));
+            }
+            digits.push(char);
+        }
+
+        if digits.is_empty() {
+            return Err(self.fmt_error(
+                token.span,
+                format!("Expected {} digits after '{}'", name, &text[..prefix]),
+            ));
+        }
+
+        Ok(match i64::from_str_radix(&digits, radix) {
+            Ok(int) => Literal::Int(int),
+            Err(_) => Literal::BigInt(BigInt::parse_bytes(digits.as_bytes(), radix).unwrap()),
+        })
+    }
+
+    /// Parse a float literal which may contain `_` separators
+    fn parse_float(&self, token: Token) -> Result<Literal, ParseError> {
+        let text = self.text(token);
+        let float = text.replace('_', "").parse::<f64>().map_err(|_| {
+            self.fmt_error(
+                token.span,
+                format!("'{}' is not a valid float literal", text),
+            )
+        })?;
+
+        if float.is_infinite() {
+            return Err(self.fmt_error(
+                token.span,
+                format!(
+                    "Float literal '{}' is out of range, floats must be between {:e} and {:e}",
+                    text,
+                    f64::MIN,
+                    f64::MAX
+                ),
+            ));
+        }
+
+        Ok(Literal::Float(float))
+    }
+
+    /// Parse the exact value of a decimal rational literal such as `0.1r`
+    fn parse_rational(text: &str) -> BigRational {
+        let text = text.trim_end_matches('r').replace('_', "");
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let numerator = format!("{}{}", whole, fraction).parse::<BigInt>().unwrap();
+        let denominator = BigInt::from(10).pow(fraction.len() as u32);
+        BigRational::new(numerator, denominator)
+    }
+
     fn parse_prefix_op(&mut self, op: TokenKind) -> ExprResult {
         self.next_token().unwrap();
 
-        // unwrapped because it cannot fail, `op` is guaranteed to be either `not` or `-`
+        // unwrapped because it cannot fail, `op` is guaranteed to be `not`, `-` or `~`
         let ((), right_bp) = op.prefix_binding_power().unwrap();
 
         let expr = Box::new(self.parse_expr(right_bp)?);
@@ -156,9 +293,9 @@ impl Parser<'_> {
 
     fn parse_grouping(&mut self) -> ExprResult {
         self.next_token().unwrap();
-        let expr = self.expr();
+        let expr = self.expr()?;
         self.consume(TokenKind::RightParen)?;
-        expr
+        Ok(expr)
     }
 
     pub fn expr(&mut self) -> ExprResult {
@@ -1,7 +1,12 @@
 pub mod expr;
 pub mod stmt;
+pub mod string;
 
-use std::iter::Peekable;
+use std::{
+    fmt,
+    iter::{Filter, Peekable},
+    ops::Range,
+};
 
 use crate::{
     ast::Stmt,
@@ -12,40 +17,103 @@ use crate::{
     },
 };
 
+/// How deeply expressions and blocks can nest, which bounds the recursion of the parser and of
+/// everything walking the syntax tree it builds
+pub const MAX_NESTING: usize = 64;
+
+/// The tokens seen by the parser, which are those of the lexer without comments
+type Tokens<'input> = Peekable<Filter<Lexer<'input>, fn(&Token) -> bool>>;
+
 /// Parser which holds the input string to extract the source text of tokens and the lexer itself
 pub struct Parser<'input> {
     input: &'input str,
-    lexer: Peekable<Lexer<'input>>,
+    lexer: Tokens<'input>,
+    /// End of the last token consumed which wasn't a newline, where a statement ends
+    end: usize,
+    /// How many expressions and blocks the one being parsed is nested inside
+    nesting: usize,
+}
+
+/// An error from parsing, which displays with the line and column it is at
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The input ended before the statement or expression being parsed did
+    UnexpectedEof,
+    Syntax {
+        span: Span,
+        /// The line (0 based) and column (0 based, in characters) of the start of `span`
+        line: usize,
+        column: usize,
+        message: String,
+    },
 }
 
-pub enum ParseError {}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "Parse error: Unexpected EOF"),
+            Self::Syntax {
+                line,
+                column,
+                message,
+                ..
+            } => write!(f, "Parse error at {}:{} - {}", line + 1, column, message),
+        }
+    }
+}
 
 impl<'input> Parser<'input> {
     pub fn new(input: &'input str) -> Self {
         Self {
             input,
-            lexer: Lexer::new(input).peekable(),
+            lexer: Lexer::new(input).filter(is_code as _).peekable(),
+            end: 0,
+            nesting: 0,
+        }
+    }
+
+    /// Create a parser for only `range` of `input`, which reports errors relative to all of `input`
+    pub fn new_in(input: &'input str, range: Range<usize>) -> Self {
+        Self {
+            input,
+            lexer: Lexer::new_in(input, range.clone())
+                .filter(is_code as _)
+                .peekable(),
+            end: range.start,
+            nesting: 0,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, String> {
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
         let mut stmts: Vec<Stmt> = Vec::new();
         loop {
             let stmt = self.parse_stmt();
             match stmt {
                 Ok(stmt) => stmts.push(stmt),
-                Err(err) => {
-                    if &err == "Parse error: Unexpected EOF" {
-                        break;
-                    }
-
-                    return Err(err);
-                }
+                Err(ParseError::UnexpectedEof) => break,
+                Err(err) => return Err(err),
             }
         }
         Ok(stmts)
     }
 
+    /// Whether `input` is the start of a statement which continues on later lines, as it has a
+    /// block without an `end` or an unterminated string
+    pub fn is_incomplete(input: &str) -> bool {
+        let mut open_blocks = 0usize;
+        for token in Lexer::new(input) {
+            match token.kind {
+                TokenKind::Begin | TokenKind::Try => open_blocks += 1,
+                TokenKind::End => open_blocks = open_blocks.saturating_sub(1),
+                // The lexer can't match a string or comment without its closing quotes or brace
+                TokenKind::Error if token.text(input).starts_with(['"', '{']) => return true,
+                _ => (),
+            }
+        }
+
+        open_blocks > 0
+    }
+
     /// Get the source text of a given token
     pub fn text(&self, token: Token) -> &'input str {
         token.text(self.input)
@@ -59,6 +127,15 @@ impl<'input> Parser<'input> {
             .unwrap_or(TokenKind::Eof)
     }
 
+    /// Look ahead to the span of the next token without consuming it
+    pub fn peek_span(&mut self) -> Span {
+        let end = self.input.len();
+        self.lexer
+            .peek()
+            .map(|token| token.span)
+            .unwrap_or_else(|| (end..end).into())
+    }
+
     /// Peek ahead to the next token and check if its `TokenKind` is `kind`
     pub fn at(&mut self, kind: TokenKind) -> bool {
         self.peek() == kind
@@ -66,11 +143,15 @@ impl<'input> Parser<'input> {
 
     /// Consume and return the next token
     pub fn next_token(&mut self) -> Option<Token> {
-        self.lexer.next()
+        let token = self.lexer.next();
+        if let Some(token) = token.filter(|token| token.kind != TokenKind::Newline) {
+            self.end = token.span.end;
+        }
+        token
     }
 
     /// Consume token and check that it's `TokenKind` is as `expected`
-    pub fn consume(&mut self, expected: TokenKind) -> Result<(), String> {
+    pub fn consume(&mut self, expected: TokenKind) -> Result<(), ParseError> {
         let token = self.next_token().unwrap();
         if token.kind != expected {
             Err(self.fmt_error(
@@ -82,9 +163,38 @@ impl<'input> Parser<'input> {
         }
     }
 
-    /// Format error with line, column and message
-    pub fn fmt_error(&self, span: Span, msg: String) -> String {
+    /// Run `parse` one level of nesting deeper, erroring instead once nested `MAX_NESTING` levels
+    pub fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.nesting == MAX_NESTING {
+            let span = self.peek_span();
+            return Err(self.fmt_error(
+                span,
+                format!("Nested more than {} levels deep", MAX_NESTING),
+            ));
+        }
+
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    /// Error with a message about `span`, finding its line and column
+    pub fn fmt_error(&self, span: Span, msg: String) -> ParseError {
         let (line, column) = span.get_line_and_column(self.input);
-        format!("Parse error at {}:{} - {}", line + 1, column, msg)
+        ParseError::Syntax {
+            span,
+            line,
+            column,
+            message: msg,
+        }
     }
 }
+
+/// Whether `token` is seen by the parser, rather than being a comment
+fn is_code(token: &Token) -> bool {
+    token.kind != TokenKind::Comment
+}
@@ -1,30 +1,42 @@
-use crate::ast::Stmt;
+use crate::ast::{Expr, Literal, Stmt, StmtKind};
 use crate::lexer::token_kind::TokenKind;
 
-use super::Parser;
+use super::{ParseError, Parser};
 
-type StmtResult = Result<Stmt, String>;
+type StmtResult = Result<Stmt, ParseError>;
+type StmtKindResult = Result<StmtKind, ParseError>;
 
-const STMT_PREFIXES: [TokenKind; 6] = [
+const STMT_PREFIXES: [TokenKind; 11] = [
     TokenKind::Set,
     TokenKind::Push,
     TokenKind::Pop,
     TokenKind::Print,
     TokenKind::Begin,
     TokenKind::Call,
+    TokenKind::Try,
+    TokenKind::Throw,
+    TokenKind::Import,
+    TokenKind::Assert,
+    TokenKind::AssertEq,
 ];
 
 impl Parser<'_> {
     pub fn parse_stmt(&mut self) -> StmtResult {
-        match self.peek() {
+        self.skip_newlines();
+        let start = self.peek_span().start;
+        let kind = match self.peek() {
             TokenKind::Set => self.parse_set(),
             TokenKind::Push => self.parse_push(),
             TokenKind::Pop => self.parse_pop_stmt(),
             TokenKind::Print => self.parse_print(),
             TokenKind::Call => self.parse_fncall(),
             TokenKind::Begin => self.parse_fndef(),
-            TokenKind::Newline => self.parse_newline(),
-            TokenKind::Eof => Err("Parse error: Unexpected EOF".to_string()),
+            TokenKind::Try => self.parse_try(),
+            TokenKind::Throw => self.parse_throw(),
+            TokenKind::Import => self.parse_import(),
+            TokenKind::Assert => self.parse_assert(),
+            TokenKind::AssertEq => self.parse_assert_eq(),
+            TokenKind::Eof => Err(ParseError::UnexpectedEof),
             _ => {
                 let token = self.next_token().unwrap();
                 Err(self.fmt_error(
@@ -32,22 +44,27 @@ impl Parser<'_> {
                     format!("Expected statement, got {}", token.kind),
                 ))
             }
-        }
+        }?;
+
+        Ok(Stmt {
+            kind,
+            span: (start..self.end).into(),
+        })
     }
 
-    fn parse_newline(&mut self) -> StmtResult {
+    fn skip_newlines(&mut self) {
         while self.peek() == TokenKind::Newline {
             self.next_token().unwrap();
         }
-        self.parse_stmt()
     }
 
+    /// Whether the next token starts a statement
     #[inline]
-    fn is_statement(&mut self) -> bool {
+    pub fn is_statement(&mut self) -> bool {
         STMT_PREFIXES.contains(&self.peek())
     }
 
-    fn ident(&mut self) -> Result<String, String> {
+    fn ident(&mut self) -> Result<String, ParseError> {
         let ident = self.next_token().unwrap();
         if let TokenKind::Ident = ident.kind {
             Ok(self.text(ident).to_string())
@@ -59,36 +76,36 @@ impl Parser<'_> {
         }
     }
 
-    fn parse_set(&mut self) -> StmtResult {
+    fn parse_set(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
 
         let text = self.ident()?;
         let expr = self.expr()?;
         self.consume(TokenKind::Newline)?;
-        Ok(Stmt::Set { ident: text, expr })
+        Ok(StmtKind::Set { ident: text, expr })
     }
 
-    fn parse_push(&mut self) -> StmtResult {
+    fn parse_push(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
         let expr = self.expr()?;
         self.consume(TokenKind::Newline)?;
-        Ok(Stmt::Push(expr))
+        Ok(StmtKind::Push(expr))
     }
 
-    fn parse_pop_stmt(&mut self) -> StmtResult {
+    fn parse_pop_stmt(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
         self.consume(TokenKind::Newline)?;
-        Ok(Stmt::Pop)
+        Ok(StmtKind::Pop)
     }
 
-    fn parse_print(&mut self) -> StmtResult {
+    fn parse_print(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
         let expr = self.expr()?;
         self.consume(TokenKind::Newline)?;
-        Ok(Stmt::Print(expr))
+        Ok(StmtKind::Print(expr))
     }
 
-    fn parse_fncall(&mut self) -> StmtResult {
+    fn parse_fncall(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
         let ident = self.next_token().unwrap();
         match ident.kind {
@@ -101,11 +118,17 @@ impl Parser<'_> {
             }
         }
 
-        let text = self.text(ident).to_string();
-        Ok(Stmt::FnCall(text))
+        // Functions from an imported module are called as `call module.function`
+        let mut text = self.text(ident).to_string();
+        while self.at(TokenKind::Dot) {
+            self.next_token().unwrap();
+            text.push('.');
+            text.push_str(&self.ident()?);
+        }
+        Ok(StmtKind::FnCall(text))
     }
 
-    fn parse_fndef(&mut self) -> StmtResult {
+    fn parse_fndef(&mut self) -> StmtKindResult {
         self.next_token().unwrap();
         let ident = self.next_token().unwrap();
         match ident.kind {
@@ -130,12 +153,114 @@ impl Parser<'_> {
         }
         self.consume(TokenKind::Newline)?;
 
+        let body = self.parse_block(TokenKind::End)?;
+        self.consume(TokenKind::End)?;
+
+        Ok(StmtKind::FnDef {
+            ident,
+            params,
+            body,
+        })
+    }
+
+    fn parse_try(&mut self) -> StmtKindResult {
+        self.next_token().unwrap();
+        self.consume(TokenKind::Newline)?;
+        let body = self.parse_block(TokenKind::Catch)?;
+
+        self.consume(TokenKind::Catch)?;
+        let ident = self.ident()?;
+        self.consume(TokenKind::Newline)?;
+        let handler = self.parse_block(TokenKind::End)?;
+        self.consume(TokenKind::End)?;
+
+        Ok(StmtKind::Try {
+            body,
+            ident,
+            handler,
+        })
+    }
+
+    fn parse_throw(&mut self) -> StmtKindResult {
+        self.next_token().unwrap();
+        let expr = self.expr()?;
+        self.consume(TokenKind::Newline)?;
+        Ok(StmtKind::Throw(expr))
+    }
+
+    fn parse_import(&mut self) -> StmtKindResult {
+        self.next_token().unwrap();
+        let token = self.next_token().unwrap();
+        let path = match token.kind {
+            TokenKind::StringLit | TokenKind::RawStringLit => match self.parse_string(token)? {
+                Expr::Literal(Literal::String(path)) => path,
+                _ => {
+                    return Err(self.fmt_error(
+                        token.span,
+                        "Import paths cannot be interpolated".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(self.fmt_error(
+                    token.span,
+                    format!("Expected path string, got {}", token.kind),
+                ))
+            }
+        };
+
+        let namespace = if self.at(TokenKind::As) {
+            self.next_token().unwrap();
+            Some(self.ident()?)
+        } else {
+            None
+        };
+        self.consume(TokenKind::Newline)?;
+
+        Ok(StmtKind::Import { path, namespace })
+    }
+
+    fn parse_assert(&mut self) -> StmtKindResult {
+        self.next_token().unwrap();
+        let expr = self.expr()?;
+        let message = self.parse_assert_message()?;
+        self.consume(TokenKind::Newline)?;
+        Ok(StmtKind::Assert { expr, message })
+    }
+
+    fn parse_assert_eq(&mut self) -> StmtKindResult {
+        self.next_token().unwrap();
+        let left = self.expr()?;
+        self.consume(TokenKind::Comma)?;
+        let right = self.expr()?;
+        let message = self.parse_assert_message()?;
+        self.consume(TokenKind::Newline)?;
+        Ok(StmtKind::AssertEq {
+            left,
+            right,
+            message,
+        })
+    }
+
+    /// Parse the optional `, message` ending an assertion
+    fn parse_assert_message(&mut self) -> Result<Option<Expr>, ParseError> {
+        if !self.at(TokenKind::Comma) {
+            return Ok(None);
+        }
+        self.next_token().unwrap();
+        self.expr().map(Some)
+    }
+
+    /// Parse statements up until `terminator`, which is left unconsumed
+    fn parse_block(&mut self, terminator: TokenKind) -> Result<Vec<Stmt>, ParseError> {
+        self.nested(|parser| parser.parse_nested_block(terminator))
+    }
+
+    fn parse_nested_block(&mut self, terminator: TokenKind) -> Result<Vec<Stmt>, ParseError> {
         let mut body = Vec::new();
         loop {
-            let stmt = self.parse_stmt()?;
-            body.push(stmt);
-
-            if let TokenKind::End = self.peek() {
+            self.skip_newlines();
+            if self.at(terminator) {
                 break;
             }
 
@@ -143,16 +268,13 @@ impl Parser<'_> {
                 let token = self.next_token().unwrap();
                 return Err(self.fmt_error(
                     token.span,
-                    format!("Expected statement or 'end', got {}", token.kind),
+                    format!("Expected statement or {}, got {}", terminator, token.kind),
                 ));
             }
+
+            body.push(self.parse_stmt()?);
         }
-        self.consume(TokenKind::End)?;
 
-        Ok(Stmt::FnDef {
-            ident,
-            params,
-            body,
-        })
+        Ok(body)
     }
 }
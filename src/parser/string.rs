@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use crate::{
+    ast::{Expr, Literal, StringPart},
+    lexer::{token_kind::TokenKind, types::Token},
+};
+
+use super::{ParseError, Parser};
+
+impl Parser<'_> {
+    /// Parse a string literal token, decoding escape sequences and interpolations
+    /// unless it is raw
+    pub(super) fn parse_string(&self, token: Token) -> Result<Expr, ParseError> {
+        let text = self.text(token);
+        let (raw, text, offset) = match token.kind {
+            TokenKind::RawStringLit => (true, &text[1..], 1),
+            _ => (false, text, 0),
+        };
+
+        let (contents, offset) = if text.starts_with(r#"""""#) && text.len() >= 6 {
+            let contents = &text[3..text.len() - 3];
+            // A newline directly after the opening quotes is not part of the string
+            match contents.strip_prefix('\n') {
+                Some(stripped) => (stripped, offset + 4),
+                None => (contents, offset + 3),
+            }
+        } else {
+            (&text[1..text.len() - 1], offset + 1)
+        };
+
+        if raw {
+            return Ok(Expr::Literal(Literal::String(contents.to_string())));
+        }
+
+        let mut parts = self.parse_string_parts(contents, token.span.start + offset)?;
+        Ok(match parts.as_mut_slice() {
+            [] => Expr::Literal(Literal::String(String::new())),
+            [StringPart::Literal(string)] => Expr::Literal(Literal::String(std::mem::take(string))),
+            _ => Expr::Interpolation(parts),
+        })
+    }
+
+    /// Decode the escape sequences in `contents`, which starts at byte `start` of the input,
+    /// splitting it into literal text and `{expression}`s, where `{{` is a literal `{`
+    fn parse_string_parts(
+        &self,
+        contents: &str,
+        start: usize,
+    ) -> Result<Vec<StringPart>, ParseError> {
+        let mut parts = Vec::new();
+        let mut string = String::with_capacity(contents.len());
+        let mut chars = contents.char_indices().peekable();
+        while let Some((index, char)) = chars.next() {
+            // A doubled brace is a literal one
+            if char == '{' && chars.next_if(|&(_, next)| next == '{').is_some() {
+                string.push('{');
+                continue;
+            }
+            if char == '{' {
+                let close = contents[index..].find('}').map(|close| index + close);
+                let close = close.ok_or_else(|| self.unclosed_brace(contents, start, index))?;
+
+                if !string.is_empty() {
+                    parts.push(StringPart::Literal(std::mem::take(&mut string)));
+                }
+                parts.push(StringPart::Expr(
+                    self.parse_interpolated_expr(start + index + 1..start + close)?,
+                ));
+
+                while chars.next_if(|&(index, _)| index <= close).is_some() {}
+                continue;
+            }
+
+            if char != '\\' {
+                string.push(char);
+                continue;
+            }
+
+            let escape = chars.next().map(|(_, char)| char);
+            string.push(match escape {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some('"') => '"',
+                Some('\'') => '\'',
+                Some('{') => '{',
+                Some('}') => '}',
+                Some('u') => {
+                    let mut end = index + 2;
+                    let mut digits = String::new();
+                    let mut closed = false;
+                    if let Some((_, '{')) = chars.peek() {
+                        chars.next();
+                        end += 1;
+                        for (_, char) in chars.by_ref() {
+                            end += char.len_utf8();
+                            if char == '}' {
+                                closed = true;
+                                break;
+                            }
+                            digits.push(char);
+                        }
+                    }
+
+                    let span = (start + index..start + end).into();
+                    if !closed || digits.is_empty() || digits.len() > 6 {
+                        return Err(self.fmt_error(
+                            span,
+                            "Unicode escapes must be of the form '\\u{XXXX}' with 1 to 6 hex digits"
+                                .to_string(),
+                        ));
+                    }
+
+                    u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| {
+                            self.fmt_error(
+                                span,
+                                format!("'{}' is not a valid unicode scalar value", digits),
+                            )
+                        })?
+                }
+                _ => {
+                    let len = escape.map_or(0, char::len_utf8);
+                    return Err(self.fmt_error(
+                        (start + index..start + index + 1 + len).into(),
+                        format!(
+                            "Invalid escape sequence '{}'",
+                            &contents[index..index + 1 + len]
+                        ),
+                    ));
+                }
+            });
+        }
+
+        if !string.is_empty() {
+            parts.push(StringPart::Literal(string));
+        }
+        Ok(parts)
+    }
+
+    /// The error for the `{` at `index` of `contents` having no `}`, which is most likely because
+    /// the interpolation contains a string literal whose opening quote ended the string instead
+    fn unclosed_brace(&self, contents: &str, start: usize, index: usize) -> ParseError {
+        let quote = start + contents.len();
+        let line_rest = self.input[quote..].lines().next().unwrap_or_default();
+        if line_rest.contains('}') {
+            return self.fmt_error(
+                (quote..quote + 1).into(),
+                "String literals are not allowed inside '{}' in a string, as their '\"' ends it (or \
+                 use '{{' or '\\{' for a literal brace)"
+                    .to_string(),
+            );
+        }
+
+        self.fmt_error(
+            (start + index..start + index + 1).into(),
+            "Unclosed '{' in string, use '{{' or '\\{' for a literal brace".to_string(),
+        )
+    }
+
+    /// Parse the expression between the braces of an interpolation, `range` being its
+    /// position in the input
+    fn parse_interpolated_expr(&self, range: Range<usize>) -> Result<Expr, ParseError> {
+        if self.input[range.clone()].trim().is_empty() {
+            return Err(self.fmt_error(
+                (range.start - 1..range.end + 1).into(),
+                "Expected expression inside '{}' in string".to_string(),
+            ));
+        }
+
+        let mut parser = Parser::new_in(self.input, range.clone());
+        let expr = parser.expr().map_err(|err| match err {
+            // The end of the interpolation is not the end of the input
+            ParseError::UnexpectedEof => self.fmt_error(
+                (range.end..range.end + 1).into(),
+                "Unexpected '}' in string, expected expression".to_string(),
+            ),
+            err => err,
+        })?;
+        let token = parser.next_token().unwrap();
+        if token.kind != TokenKind::Eof {
+            return Err(parser.fmt_error(
+                token.span,
+                format!(
+                    "Expected '}}' after expression in string, got {}",
+                    token.kind
+                ),
+            ));
+        }
+
+        Ok(expr)
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use stacc::{
+    ast::Stmt,
+    interpreter::{hook::Hook, Interpreter, RuntimeError},
+};
+
+use crate::run;
+
+/// The name of the root of every stack, for time spent outside any function
+const ROOT: &str = "main";
+
+/// Run `source` while recording how often and for how long each function is called, then print
+/// the results to stderr and write them to `folded` in the folded stack format of flamegraph
+/// tools, returning the code to exit with
+pub fn profile(
+    mut interpreter: Interpreter,
+    source: &str,
+    table: bool,
+    folded: Option<PathBuf>,
+) -> i32 {
+    let profile = Rc::new(RefCell::new(Profile::new(source)));
+    interpreter.set_hook(Box::new(Profiler(profile.clone())));
+    let code = run(&mut interpreter, source);
+    interpreter.take_hook();
+
+    let mut profile = profile.borrow_mut();
+    profile.finish();
+    if table {
+        profile.print();
+    }
+    if let Some(path) = folded {
+        if let Err(err) = fs::write(&path, profile.folded()) {
+            eprintln!("{}: {}", path.display(), err);
+        }
+    }
+    code
+}
+
+#[derive(Default)]
+struct FunctionStats {
+    calls: u64,
+    /// Time spent in the function including the functions it called
+    inclusive: Duration,
+    /// Time spent in the function itself
+    exclusive: Duration,
+}
+
+/// A call which hasn't returned yet
+struct Frame {
+    name: String,
+    start: Instant,
+    /// Time spent in the functions this one called
+    children: Duration,
+}
+
+struct Profile {
+    source: String,
+    lines: Vec<String>,
+    functions: HashMap<String, FunctionStats>,
+    /// The number of statements run on each line (0 based) of the main program
+    line_counts: BTreeMap<usize, u64>,
+    /// Exclusive time by the names of the calls leading to it, joined by semicolons
+    stacks: HashMap<String, Duration>,
+    /// The calls which haven't returned yet, starting with the root
+    frames: Vec<Frame>,
+}
+
+/// Records into a profile which is shared with `profile`, as the interpreter owns its hook
+struct Profiler(Rc<RefCell<Profile>>);
+
+impl Hook for Profiler {
+    fn before_stmt(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: &Stmt,
+    ) -> Result<(), RuntimeError> {
+        // Statements of modules and the prelude aren't from the file being run
+        if !interpreter.in_module() {
+            let mut profile = self.0.borrow_mut();
+            let line = stmt.span.get_line_and_column(&profile.source).0;
+            *profile.line_counts.entry(line).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn enter_fn(&mut self, _interpreter: &mut Interpreter, name: &str) -> Result<(), RuntimeError> {
+        self.0.borrow_mut().frames.push(Frame {
+            name: name.to_string(),
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+        Ok(())
+    }
+
+    fn exit_fn(&mut self, _interpreter: &mut Interpreter, _name: &str) {
+        self.0.borrow_mut().exit();
+    }
+}
+
+impl Profile {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            lines: source.lines().map(str::to_string).collect(),
+            functions: HashMap::new(),
+            line_counts: BTreeMap::new(),
+            stacks: HashMap::new(),
+            frames: vec![Frame {
+                name: ROOT.to_string(),
+                start: Instant::now(),
+                children: Duration::ZERO,
+            }],
+        }
+    }
+
+    /// Account for the innermost call returning
+    fn exit(&mut self) {
+        let stack = self
+            .frames
+            .iter()
+            .map(|frame| frame.name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let elapsed = frame.start.elapsed();
+        let exclusive = elapsed.saturating_sub(frame.children);
+        *self.stacks.entry(stack).or_default() += exclusive;
+        match self.frames.last_mut() {
+            Some(caller) => caller.children += elapsed,
+            // The root isn't a function
+            None => return,
+        }
+
+        let stats = self.functions.entry(frame.name.clone()).or_default();
+        stats.calls += 1;
+        stats.exclusive += exclusive;
+        // A recursive call's time is already included in its outermost call
+        if !self.frames[1..]
+            .iter()
+            .any(|caller| caller.name == frame.name)
+        {
+            stats.inclusive += elapsed;
+        }
+    }
+
+    /// Account for the time spent outside of any function, once the program has finished
+    fn finish(&mut self) {
+        while !self.frames.is_empty() {
+            self.exit();
+        }
+    }
+
+    /// Print the functions by exclusive time, then the lines by the number of statements run
+    fn print(&self) {
+        let mut functions = self.functions.iter().collect::<Vec<_>>();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then(a.0.cmp(b.0)));
+        let name_width = functions
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(Some("Function".len()))
+            .max()
+            .unwrap_or_default();
+
+        eprintln!(
+            "{:<width$}  {:>8}  {:>12}  {:>12}",
+            "Function",
+            "Calls",
+            "Inclusive",
+            "Exclusive",
+            width = name_width
+        );
+        for (name, stats) in functions {
+            eprintln!(
+                "{:<width$}  {:>8}  {:>12}  {:>12}",
+                name,
+                stats.calls,
+                format!("{:.3?}", stats.inclusive),
+                format!("{:.3?}", stats.exclusive),
+                width = name_width
+            );
+        }
+
+        let mut line_counts = self.line_counts.iter().collect::<Vec<_>>();
+        line_counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        eprintln!("\n{:>6}  {:>8}  Source", "Line", "Count");
+        for (line, count) in line_counts {
+            let text = self.lines.get(*line).map_or("", |text| text.trim());
+            eprintln!("{:>6}  {:>8}  {}", line + 1, count, text);
+        }
+    }
+
+    /// Each stack and the time spent in it in microseconds, a line each, sorted by stack
+    fn folded(&self) -> String {
+        let mut stacks = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| (stack, time.as_micros()))
+            .filter(|(_, micros)| *micros > 0)
+            .collect::<Vec<_>>();
+        stacks.sort();
+        stacks
+            .iter()
+            .map(|(stack, micros)| format!("{} {}\n", stack, micros))
+            .collect()
+    }
+}
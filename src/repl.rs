@@ -0,0 +1,395 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context, Editor, Helper,
+};
+use stacc::{
+    ast::Expr,
+    interpreter::{value::Value, Interpreter, RuntimeError},
+    lexer::token_kind::{TokenKind, KEYWORDS},
+    parser::{ParseError, Parser},
+};
+
+use crate::unwrap;
+
+/// Usage of the commands, which start with a colon rather than being run as statements
+const HELP: &str = "\
+:load <file>  run a script in this session
+:reset        clear every variable, the stack and imported modules
+:stack        show the stack
+:vars         show the variables
+:ast <code>   show the parsed form of an expression or statements
+:time <code>  run statements and show how long they took
+:type <expr>  show the type of an expression's value
+:dump         toggle showing the variables and stack after each statement
+:help         show this message";
+
+pub fn repl(interpreter: Interpreter) {
+    let mut repl = Repl::new(interpreter);
+    repl.run();
+    repl.save_history();
+}
+
+struct Repl {
+    interpreter: Interpreter,
+    editor: Editor<StaccHelper, DefaultHistory>,
+    history: Option<PathBuf>,
+    /// Whether a statement is being evaluated, so that Ctrl-C should interrupt it
+    evaluating: Arc<AtomicBool>,
+    /// Whether to print the variables and stack after each statement
+    dump_state: bool,
+}
+
+impl Repl {
+    fn new(interpreter: Interpreter) -> Self {
+        // Ctrl-C aborts the statement being evaluated, while at the prompt rustyline reads it
+        // as a key, so that `read_stmt` abandons the input and the history is still saved on exit
+        let evaluating = Arc::new(AtomicBool::new(false));
+        {
+            let evaluating = evaluating.clone();
+            let interrupt = interpreter.interrupt_handle();
+            unwrap(ctrlc::set_handler(move || {
+                if evaluating.load(Ordering::SeqCst) {
+                    interrupt.interrupt();
+                }
+            }));
+        }
+
+        let mut editor = unwrap(Editor::new());
+        editor.set_helper(Some(StaccHelper::default()));
+        let history = history_path();
+        if let Some(history) = &history {
+            // There is no history the first time the repl is run
+            let _ = editor.load_history(history);
+        }
+
+        Self {
+            interpreter,
+            editor,
+            history,
+            evaluating,
+            dump_state: true,
+        }
+    }
+
+    fn run(&mut self) {
+        loop {
+            self.editor.helper_mut().unwrap().names = completion_names(&self.interpreter);
+            let input = match read_stmt(&mut self.editor) {
+                Some(input) => input,
+                None => break,
+            };
+
+            match input.trim().strip_prefix(':') {
+                Some(command) => self.command(command),
+                None => {
+                    if self.eval(&input).is_some() && self.dump_state {
+                        self.interpreter.print_state();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse and run `input`, returning `None` if it failed
+    fn eval(&mut self, input: &str) -> Option<()> {
+        let stmts = match Parser::new(input).parse() {
+            Ok(stmts) if stmts.is_empty() => return None,
+            Ok(stmts) => stmts,
+            Err(err) => {
+                eprintln!("{}", err);
+                return None;
+            }
+        };
+
+        self.interpreter.refuel();
+        self.evaluating.store(true, Ordering::SeqCst);
+        let result = self.interpreter.run(&stmts);
+        self.evaluating.store(false, Ordering::SeqCst);
+        self.interpreter.interrupt_handle().reset();
+
+        match result {
+            Ok(()) => Some(()),
+            Err(RuntimeError::Exit { code }) => {
+                self.save_history();
+                process::exit(code)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        }
+    }
+
+    fn command(&mut self, command: &str) {
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (command, ""),
+        };
+        let needs_arg = matches!(name, "load" | "ast" | "time" | "type");
+        if needs_arg && arg.is_empty() {
+            let usage = match name {
+                "load" => "file",
+                "type" => "expr",
+                _ => "code",
+            };
+            eprintln!("Usage: :{} <{}>", name, usage);
+            return;
+        }
+
+        match name {
+            "load" => self.load(arg),
+            "reset" => self.interpreter.reset(),
+            "stack" => {
+                for value in self.interpreter.state().1.iter().rev() {
+                    println!("{}", value);
+                }
+            }
+            "vars" => {
+                let mut variables = self.interpreter.state().0.into_iter().collect::<Vec<_>>();
+                variables.sort_by(|a, b| a.0.cmp(&b.0));
+                for (name, value) in variables {
+                    println!("{} = {}", name, value);
+                }
+            }
+            "ast" => match parse_ast(arg) {
+                Ok(ast) => println!("{}", ast),
+                Err(err) => eprintln!("{}", err),
+            },
+            "time" => {
+                let start = Instant::now();
+                if self.eval(&format!("{}\n", arg)).is_some() {
+                    let statements = self.interpreter.fuel_used();
+                    println!(
+                        "Took {:?} to run {} statement{}",
+                        start.elapsed(),
+                        statements,
+                        if statements == 1 { "" } else { "s" }
+                    );
+                }
+            }
+            "type" => match parse_expr(arg) {
+                Ok(expr) => match self.interpreter.eval_expr(&expr) {
+                    Ok(value) => println!("{}", value.type_name()),
+                    Err(err) => eprintln!("{}", err),
+                },
+                Err(err) => eprintln!("{}", err),
+            },
+            "dump" => {
+                self.dump_state = !self.dump_state;
+                println!(
+                    "State is {} shown after each statement",
+                    if self.dump_state { "now" } else { "no longer" }
+                );
+            }
+            "help" => println!("{}", HELP),
+            _ => eprintln!("Unknown command :{}, try :help", name),
+        }
+    }
+
+    fn load(&mut self, path: &str) {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                return;
+            }
+        };
+
+        // Imports in the file are relative to it, rather than the working directory
+        self.interpreter.set_path(path);
+        let result = self.eval(&contents);
+        self.interpreter.clear_path();
+        if result.is_some() && self.dump_state {
+            self.interpreter.print_state();
+        }
+    }
+
+    fn save_history(&mut self) {
+        if let Some(history) = &self.history {
+            if let Err(err) = self.editor.save_history(history) {
+                eprintln!("Could not save history: {}", err);
+            }
+        }
+    }
+}
+
+/// Parse a single expression, which must be all of `text`
+pub fn parse_expr(text: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(text);
+    let expr = parser.expr().map_err(|err| match err {
+        ParseError::UnexpectedEof => {
+            let end = text.len().saturating_sub(1);
+            parser.fmt_error(
+                (end..end).into(),
+                "Expected expression, got end of input".to_string(),
+            )
+        }
+        err => err,
+    })?;
+    let token = parser.next_token().unwrap();
+    if token.kind != TokenKind::Eof {
+        return Err(parser.fmt_error(
+            token.span,
+            format!("Expected end of expression, got {}", token.kind),
+        ));
+    }
+    Ok(expr)
+}
+
+/// The S-expression form of statements, or of an expression
+fn parse_ast(text: &str) -> Result<String, ParseError> {
+    let text = format!("{}\n", text);
+    if !Parser::new(&text).is_statement() {
+        return parse_expr(text.trim_end()).map(|expr| expr.to_string());
+    }
+
+    let stmts = Parser::new(&text).parse()?;
+    Ok(stmts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Read lines until they form complete statements, e.g. a whole `begin ... end` block,
+/// returning `None` once the input is closed
+fn read_stmt(editor: &mut Editor<StaccHelper, DefaultHistory>) -> Option<String> {
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                input.push_str(&line);
+                input.push('\n');
+            }
+            // Ctrl-C abandons the statement being typed
+            Err(ReadlineError::Interrupted) => return Some(String::new()),
+            // Ctrl-D exits, or abandons the statement if part of one has been typed
+            Err(ReadlineError::Eof) if input.is_empty() => return None,
+            Err(ReadlineError::Eof) => return Some(String::new()),
+            Err(err) => {
+                eprintln!("{}", err);
+                return None;
+            }
+        }
+
+        // Commands are always a single line
+        if input.starts_with(':') || !Parser::is_incomplete(&input) {
+            return Some(input);
+        }
+    }
+}
+
+/// The history file, in the user's data directory
+fn history_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("stacc");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("history.txt"))
+}
+
+/// Every variable and function name, along with the members of imported modules
+/// as `module.member`
+fn completion_names(interpreter: &Interpreter) -> Vec<String> {
+    let mut names = Vec::new();
+    for name in interpreter.names() {
+        if let Some(Value::Module(module)) = interpreter.variable(&name) {
+            names.extend(
+                module
+                    .globals
+                    .borrow()
+                    .keys()
+                    .map(|member| format!("{}.{}", name, member)),
+            );
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Completes keywords and the names defined when the line was started
+#[derive(Default)]
+struct StaccHelper {
+    names: Vec<String>,
+}
+
+impl Completer for StaccHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = word_start(line, pos);
+        let word = &line[start..pos];
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+
+        let mut candidates = KEYWORDS
+            .iter()
+            .copied()
+            .chain(self.names.iter().map(String::as_str))
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.display.cmp(&b.display));
+        candidates.dedup_by(|a, b| a.display == b.display);
+        Ok((start, candidates))
+    }
+}
+
+/// The byte index of the start of the name being typed before `pos`
+fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .find(|&(_, char)| !(char.is_alphanumeric() || char == '_' || char == '.'))
+        .map_or(0, |(index, char)| index + char.len_utf8())
+}
+
+impl Hinter for StaccHelper {
+    type Hint = String;
+}
+
+impl Highlighter for StaccHelper {}
+
+impl Validator for StaccHelper {}
+
+impl Helper for StaccHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_start_after_a_multi_byte_character() {
+        let line = "print \"→pr";
+        assert_eq!(&line[word_start(line, line.len())..], "pr");
+        assert_eq!(word_start("pr", 2), 0);
+        assert_eq!(&line[word_start(line, 7)..7], "");
+    }
+}
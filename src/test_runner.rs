@@ -0,0 +1,110 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use stacc::{
+    ast::{Stmt, StmtKind},
+    interpreter::{Interpreter, RuntimeError},
+    lexer::types::Span,
+    parser::Parser,
+};
+
+use crate::find_scripts;
+
+/// The prefix of the names of test functions
+const TEST_PREFIX: &str = "test_";
+
+/// Run every test function in the `.stacc` files in `paths` (searching directories
+/// recursively), each in a fresh interpreter made by `new_interpreter`, and return the code to
+/// exit with
+///
+/// Test functions are the top-level functions named `test_*` with no parameters, and pass if
+/// they finish without an error, after the rest of the file's top-level code has been run
+pub fn test(paths: &[PathBuf], new_interpreter: impl Fn() -> Interpreter) -> i32 {
+    let start = Instant::now();
+    let mut files = Vec::new();
+    for path in paths {
+        if let Err(err) = find_scripts(path, &mut files) {
+            eprintln!("{}: {}", path.display(), err);
+            return 1;
+        }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    for file in &files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                println!("FAIL {}\n  {}", file.display(), err);
+                failed += 1;
+                continue;
+            }
+        };
+        let stmts = match Parser::new(&source).parse() {
+            Ok(stmts) => stmts,
+            Err(err) => {
+                println!("FAIL {}\n  {}", file.display(), err);
+                failed += 1;
+                continue;
+            }
+        };
+
+        for (ident, params, span) in stmts.iter().filter_map(test_function) {
+            let name = format!("{}::{}", file.display(), ident);
+            if !params.is_empty() {
+                println!("FAIL {}\n  Test functions can't take parameters", name);
+                failed += 1;
+                continue;
+            }
+
+            let mut interpreter = new_interpreter();
+            interpreter.set_path(file);
+            let call = Stmt {
+                kind: StmtKind::FnCall(ident.to_string()),
+                span,
+            };
+            match interpreter
+                .run(&stmts)
+                .and_then(|()| interpreter.run_one(&call))
+            {
+                Ok(()) => {
+                    println!("PASS {}", name);
+                    passed += 1;
+                }
+                Err(err) => {
+                    println!("FAIL {}\n  {}", name, err);
+                    if let RuntimeError::AssertionFailed { span, .. } = err {
+                        let (line, column) = span.get_line_and_column(&source);
+                        println!("    at {}:{}:{}", file.display(), line + 1, column + 1);
+                        if let Some(text) = source.lines().nth(line) {
+                            println!("{:>8} | {}", line + 1, text);
+                        }
+                    }
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if passed + failed == 0 {
+        println!("No tests found");
+        return 0;
+    }
+    println!(
+        "\n{} passed, {} failed in {} file{} ({:.3?})",
+        passed,
+        failed,
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        start.elapsed()
+    );
+    i32::from(failed > 0)
+}
+
+/// The name, parameters and span of `stmt` if it defines a test function
+fn test_function(stmt: &Stmt) -> Option<(&str, &[String], Span)> {
+    match &stmt.kind {
+        StmtKind::FnDef { ident, params, .. } if ident.starts_with(TEST_PREFIX) => {
+            Some((ident, params, stmt.span))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,63 @@
+use clap::ValueEnum;
+use serde_json::json;
+use stacc::{
+    ast::Stmt,
+    interpreter::{builtins::json::to_json, hook::Hook, value::Value, Interpreter},
+};
+
+/// How each traced statement is printed
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// The statement indented by the call depth, followed by the stack
+    Text,
+    /// A JSON object per line with the depth, line, statement and stack
+    Json,
+}
+
+/// Prints every statement to stderr after it runs, along with the stack of the call-frame it
+/// ran in, so a function's body is printed before the `call` of it
+pub struct Tracer {
+    source: String,
+    format: Format,
+}
+
+impl Tracer {
+    pub fn new(source: &str, format: Format) -> Self {
+        Self {
+            source: source.to_string(),
+            format,
+        }
+    }
+}
+
+impl Hook for Tracer {
+    fn after_stmt(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) {
+        let depth = interpreter.depth();
+        let stack = interpreter.frame().1;
+        match self.format {
+            Format::Text => eprintln!("{}{}  {}", "  ".repeat(depth), stmt, Value::List(stack)),
+            Format::Json => {
+                // Statements of modules and the prelude aren't from the file being run
+                let line = if interpreter.in_module() {
+                    None
+                } else {
+                    Some(stmt.span.get_line_and_column(&self.source).0 + 1)
+                };
+                // Values with no JSON equivalent, like functions, are given as they're printed
+                let stack = stack
+                    .iter()
+                    .map(|value| to_json(value).unwrap_or_else(|_| json!(value.to_string())))
+                    .collect::<Vec<_>>();
+                eprintln!(
+                    "{}",
+                    json!({
+                        "depth": depth,
+                        "line": line,
+                        "stmt": stmt.to_string(),
+                        "stack": stack,
+                    })
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `stacc fmt`, which has to give the same result when run again on its output
+
+use std::fs;
+
+use stacc::cst::format::format;
+
+/// Format `input`, checking that formatting the result changes nothing
+fn formatted(input: &str) -> String {
+    let once = format(input).unwrap();
+    let twice = format(&once).unwrap();
+    assert_eq!(once, twice, "formatting {:?} again changed it", input);
+    once
+}
+
+#[test]
+fn comments_and_blank_lines() {
+    let input = "{ header }\n\n\n   \nbegin  f:x  y { params }\n  \n\t\n  push x+y\n\n  { inside }\n   \nend\n \n\nset a  -1 { trailing }\n{ multi\n  line }\n\n";
+    assert_eq!(
+        formatted(input),
+        "{ header }\n\nbegin f: x y { params }\n    push x + y\n\n    { inside }\nend\n\nset a -1 { trailing }\n{ multi\n  line }\n"
+    );
+}
+
+#[test]
+fn blank_lines_of_whitespace_inside_blocks() {
+    let input =
+        "try\n    \n  push 1\n  \t \n  \n  push 2\n    \ncatch e\n   \n  print e\n  \nend\n";
+    assert_eq!(
+        formatted(input),
+        "try\n    push 1\n\n    push 2\ncatch e\n    print e\nend\n"
+    );
+}
+
+#[test]
+fn crlf_line_endings_are_kept() {
+    let input = "begin f:\r\n  \r\n\r\n  push 1 { one }\r\n\r\n   \r\n  push 2\r\nend\r\n";
+    assert_eq!(
+        formatted(input),
+        "begin f:\r\n    push 1 { one }\r\n\r\n    push 2\r\nend\r\n"
+    );
+}
+
+#[test]
+fn examples_are_formatted_idempotently() {
+    let mut examples = fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "stacc")
+        })
+        .peekable();
+    assert!(examples.peek().is_some());
+    for path in examples {
+        formatted(&fs::read_to_string(&path).unwrap());
+    }
+}
@@ -0,0 +1,168 @@
+//! Importing files, relative to the importer and through `STACC_PATH`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    rc::Rc,
+};
+
+use stacc::{
+    interpreter::{capabilities::Capabilities, value::Value, Interpreter, RuntimeError},
+    parser::Parser,
+};
+
+/// A fresh directory of files for one test, removed when dropped
+struct Files(PathBuf);
+
+impl Files {
+    fn new(test: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("stacc-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        Self(dir.canonicalize().unwrap())
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for Files {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run the file at `path` as `stacc run` would
+fn run_file(path: &Path) -> (Interpreter, Result<(), RuntimeError>) {
+    let source = fs::read_to_string(path).unwrap();
+    let stmts = Parser::new(&source).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_capabilities(Capabilities::all());
+    interpreter.set_path(path);
+    let result = interpreter.run(&stmts);
+    (interpreter, result)
+}
+
+#[test]
+fn imports_are_relative_to_the_importing_file() {
+    let files = Files::new(
+        "relative",
+        &[
+            (
+                "main.stacc",
+                "import \"lib/shapes.stacc\"\nset area shapes.area\n",
+            ),
+            (
+                "lib/shapes.stacc",
+                "import \"units.stacc\"\nset area 3 * units.scale\n",
+            ),
+            ("lib/units.stacc", "set scale 2\n"),
+        ],
+    );
+    let (interpreter, result) = run_file(&files.path("main.stacc"));
+    result.unwrap();
+    assert_eq!(interpreter.variable("area").unwrap().to_string(), "6");
+}
+
+#[test]
+fn import_cycles_are_errors() {
+    let files = Files::new(
+        "cycle",
+        &[
+            ("a.stacc", "import \"b.stacc\"\n"),
+            ("b.stacc", "import \"a.stacc\"\n"),
+        ],
+    );
+    let (_, result) = run_file(&files.path("a.stacc"));
+    let (a, b) = (files.path("a.stacc"), files.path("b.stacc"));
+    match result {
+        Err(RuntimeError::Import { path, message }) => {
+            assert_eq!(path, "a.stacc");
+            assert_eq!(
+                message,
+                format!(
+                    "import cycle {} -> {} -> {}",
+                    a.display(),
+                    b.display(),
+                    a.display()
+                )
+            );
+        }
+        result => panic!("the import cycle should fail, got {:?}", result),
+    }
+
+    // A file importing itself is the smallest cycle
+    let files = Files::new("self", &[("a.stacc", "import \"a.stacc\"\n")]);
+    let (_, result) = run_file(&files.path("a.stacc"));
+    assert!(
+        matches!(&result, Err(RuntimeError::Import { message, .. }) if message.starts_with("import cycle")),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn a_file_imported_twice_is_run_once() {
+    let files = Files::new(
+        "once",
+        &[
+            (
+                "main.stacc",
+                "import \"counter.stacc\" as first\nimport \"lib/../counter.stacc\" as second\n",
+            ),
+            ("counter.stacc", "set loaded true\n"),
+            ("lib/.keep", ""),
+        ],
+    );
+    let (interpreter, result) = run_file(&files.path("main.stacc"));
+    result.unwrap();
+    match (
+        interpreter.variable("first"),
+        interpreter.variable("second"),
+    ) {
+        (Some(Value::Module(first)), Some(Value::Module(second))) => {
+            assert!(Rc::ptr_eq(&first.globals, &second.globals));
+        }
+        modules => panic!("both imports should be modules, got {:?}", modules),
+    }
+}
+
+#[test]
+fn stacc_path_is_searched_after_the_importing_file() {
+    let files = Files::new(
+        "search-path",
+        &[
+            (
+                "project/main.stacc",
+                "import \"shapes.stacc\"\npush 3\ncall shapes.square\nprint pop\n",
+            ),
+            ("lib/shapes.stacc", "begin square: x\n  push x * x\nend\n"),
+        ],
+    );
+    let run = |search_path: Option<PathBuf>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_stacc"));
+        command.arg(files.path("project/main.stacc"));
+        match search_path {
+            Some(search_path) => command.env("STACC_PATH", search_path),
+            None => command.env_remove("STACC_PATH"),
+        };
+        command.output().unwrap()
+    };
+
+    let output = run(Some(files.path("lib")));
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "9\n");
+
+    let output = run(None);
+    assert_eq!(output.status.code(), Some(70));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Cannot import \"shapes.stacc\": file not found relative to the importing file or in \
+         STACC_PATH"
+    ));
+}
@@ -0,0 +1,70 @@
+//! Stopping a program which would otherwise run for practically forever
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use stacc::{
+    interpreter::{limits::Limits, Interpreter, RuntimeError},
+    parser::Parser,
+};
+
+/// Every call makes two more until the call depth is reached, which is caught so it carries on
+const FOREVER: &str = "begin f:
+  try
+    call f
+  catch e
+  end
+  try
+    call f
+  catch e
+  end
+end
+call f
+";
+
+#[test]
+fn timeout() {
+    let stmts = Parser::new(FOREVER).parse().unwrap();
+    let mut interpreter = Interpreter::with_limits(Limits {
+        timeout: Some(Duration::from_millis(50)),
+        ..Limits::default()
+    });
+
+    // The deadline is relative to the start of each run
+    for _ in 0..2 {
+        let start = Instant::now();
+        let result = interpreter.run(&stmts);
+        assert!(
+            matches!(result, Err(RuntimeError::Interrupted)),
+            "{:?}",
+            result
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
+
+#[test]
+fn interrupt_from_another_thread() {
+    let stmts = Parser::new(FOREVER).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    let handle = interpreter.interrupt_handle();
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+
+    let result = interpreter.run(&stmts);
+    assert!(
+        matches!(result, Err(RuntimeError::Interrupted)),
+        "{:?}",
+        result
+    );
+    interrupter.join().unwrap();
+
+    // Stopping consumes the interrupt, so the interpreter can carry on
+    assert!(!interpreter.interrupt_handle().is_interrupted());
+    let stmts = Parser::new("push 1\n").parse().unwrap();
+    interpreter.run(&stmts).unwrap();
+}
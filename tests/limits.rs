@@ -0,0 +1,175 @@
+//! Programs which would exhaust the native stack or the interpreter's limits
+
+use std::thread;
+
+use stacc::{
+    interpreter::{limits::Limits, Interpreter, RuntimeError},
+    parser::{ParseError, Parser, MAX_NESTING},
+};
+
+fn parse_error(source: &str) -> String {
+    match Parser::new(source).parse() {
+        Err(err @ ParseError::Syntax { .. }) => err.to_string(),
+        result => panic!("parsing should fail, got {:?}", result),
+    }
+}
+
+/// The error from running `source` under `limits`
+fn run_error(limits: Limits, source: &str) -> RuntimeError {
+    let stmts = Parser::new(source).parse().unwrap();
+    match Interpreter::with_limits(limits).run(&stmts) {
+        Ok(()) => panic!("{:?} should fail", source),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn fuel() {
+    let limits = Limits {
+        fuel: Some(10),
+        ..Limits::default()
+    };
+    let err = run_error(limits, &"push 1\n".repeat(11));
+    assert!(
+        matches!(err, RuntimeError::OutOfFuel { limit: 10 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn call_depth() {
+    let limits = Limits {
+        max_call_depth: 8,
+        ..Limits::default()
+    };
+    let err = run_error(limits, "begin f:\n  call f\nend\ncall f\n");
+    assert!(
+        matches!(err, RuntimeError::CallDepthExceeded { limit: 8 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn frame_stack() {
+    let limits = Limits {
+        max_frame_stack: Some(3),
+        ..Limits::default()
+    };
+    let err = run_error(limits, &"push 1\n".repeat(4));
+    assert!(
+        matches!(err, RuntimeError::FrameStackOverflow { limit: 3 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn total_stack() {
+    let limits = Limits {
+        max_frame_stack: Some(3),
+        max_total_stack: Some(4),
+        ..Limits::default()
+    };
+    // Neither call-frame's stack is over its limit, but together they are
+    let source = "begin f:\n  push 1\n  push 2\n  push 3\nend\npush 1\npush 2\ncall f\n";
+    let err = run_error(limits, source);
+    assert!(
+        matches!(err, RuntimeError::TotalStackOverflow { limit: 4 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn string_len() {
+    let limits = Limits {
+        max_string_len: Some(5),
+        ..Limits::default()
+    };
+    let err = run_error(limits, "push \"abc\" + \"def\"\n");
+    assert!(
+        matches!(err, RuntimeError::StringTooLong { len: 6, limit: 5 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn list_len() {
+    let limits = Limits {
+        max_list_len: Some(2),
+        ..Limits::default()
+    };
+    let err = run_error(limits, "push \",\"\npush \"a,b,c\"\ncall split\n");
+    assert!(
+        matches!(err, RuntimeError::ListTooLong { len: 3, limit: 2 }),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn deeply_nested_expressions_are_parse_errors() {
+    let source = format!("print {}1{}\n", "(".repeat(5000), ")".repeat(5000));
+    assert!(parse_error(&source).ends_with("Nested more than 64 levels deep"));
+
+    let source = format!("print {}1\n", "-".repeat(100_000));
+    assert!(parse_error(&source).ends_with("Nested more than 64 levels deep"));
+}
+
+#[test]
+fn deeply_nested_blocks_are_parse_errors() {
+    let source = format!(
+        "{}print 1\n{}",
+        "try\n".repeat(5000),
+        "catch e\nend\n".repeat(5000)
+    );
+    assert!(parse_error(&source).ends_with("Nested more than 64 levels deep"));
+}
+
+#[test]
+fn nesting_up_to_the_limit_runs() {
+    // The statement's expression is a level itself
+    let depth = MAX_NESTING - 1;
+    let source = format!("push {}1{}\n", "(".repeat(depth), ")".repeat(depth));
+    let stmts = Parser::new(&source).parse().unwrap();
+    Interpreter::new().run(&stmts).unwrap();
+
+    let source = format!("push {}1\n", "-".repeat(depth));
+    let stmts = Parser::new(&source).parse().unwrap();
+    Interpreter::new().run(&stmts).unwrap();
+}
+
+/// Run `source` on a thread with the default 2 MiB stack of a spawned thread, returning its error
+fn run_on_thread(source: String) -> String {
+    thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(move || {
+            let stmts = Parser::new(&source).parse().unwrap();
+            match Interpreter::new().run(&stmts) {
+                Ok(()) => panic!("{:?} should fail", source),
+                Err(err) => err.to_string(),
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn unbounded_recursion_reaches_the_call_depth_before_overflowing() {
+    let recurse = "push n + 1\ncall f\n";
+    // The function's body and the statement's expression are a level each
+    let deepest_expr = format!("push {}n\n", "-".repeat(MAX_NESTING - 2));
+    let try_twice = format!(
+        "try\ntry\n{}catch e\nthrow e\nend\ncatch e\nthrow e\nend\n",
+        recurse
+    );
+    for body in [recurse.to_string(), deepest_expr + recurse, try_twice] {
+        let source = format!("begin f: n\n{}end\npush 0\ncall f\n", body);
+        let err = run_on_thread(source);
+        assert!(err.contains("Exceeded the call depth of 256"), "{}", err);
+    }
+}
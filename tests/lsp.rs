@@ -0,0 +1,244 @@
+//! Drives `stacc lsp` over stdio like an editor would
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///project/main.stacc";
+
+const SOURCE: &str = "{ Double the top of the stack }
+begin double: x
+  push x*2
+end
+
+set total 1
+set total total + 1
+begin show:
+    set total \"local\"
+    print total
+end
+call double
+";
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_stacc"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("stacc lsp should start");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut len = None;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                len = Some(value.parse().unwrap());
+            }
+        }
+
+        let mut body = vec![0; len.expect("a message should have a Content-Length")];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Send a request and return its result, skipping any notifications before the response
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                assert!(
+                    message.get("error").is_none(),
+                    "{} failed: {}",
+                    method,
+                    message
+                );
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// The diagnostics of the next notification, which should be for `URI`
+    fn diagnostics(&mut self) -> Value {
+        let message = self.receive();
+        assert_eq!(message["method"], "textDocument/publishDiagnostics");
+        assert_eq!(message["params"]["uri"], URI);
+        message["params"]["diagnostics"].clone()
+    }
+}
+
+fn position(line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character },
+    })
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn lsp() {
+    let mut client = Client::start();
+    let result = client.request(
+        "initialize",
+        json!({ "processId": null, "rootUri": null, "capabilities": {} }),
+    );
+    let capabilities = &result["capabilities"];
+    for provider in [
+        "definitionProvider",
+        "hoverProvider",
+        "documentSymbolProvider",
+        "documentFormattingProvider",
+    ] {
+        assert_eq!(capabilities[provider], true, "{}", provider);
+    }
+    client.notify("initialized", json!({}));
+
+    client.notify(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": { "uri": URI, "languageId": "stacc", "version": 1, "text": SOURCE },
+        }),
+    );
+    assert_eq!(client.diagnostics(), json!([]));
+
+    // `call double` goes to the function's name
+    let result = client.request("textDocument/definition", position(11, 7));
+    assert_eq!(result["uri"], URI);
+    assert_eq!(result["range"], range((1, 6), (1, 12)));
+
+    // `total` inside `show` is its own variable, and at the top level the global one
+    let result = client.request("textDocument/definition", position(9, 11));
+    assert_eq!(result["range"], range((8, 8), (8, 13)));
+    let result = client.request("textDocument/definition", position(6, 12));
+    assert_eq!(result["range"], range((5, 4), (5, 9)));
+
+    // A parameter
+    let result = client.request("textDocument/definition", position(2, 7));
+    assert_eq!(result["range"], range((1, 14), (1, 15)));
+
+    let result = client.request("textDocument/hover", position(11, 7));
+    assert_eq!(
+        result["contents"]["value"],
+        "```stacc\nbegin double: x\n```\n\nDouble the top of the stack"
+    );
+    let result = client.request("textDocument/hover", position(0, 0));
+    assert_eq!(result, Value::Null);
+
+    let result = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let names = |symbols: &Value| {
+        symbols
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| symbol["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&result), ["double", "total", "show"]);
+    assert_eq!(names(&result[0]["children"]), ["x"]);
+    assert_eq!(names(&result[2]["children"]), ["total"]);
+
+    let result = client.request("textDocument/completion", position(11, 0));
+    let labels = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    for label in ["begin", "double", "show", "total", "square", "len"] {
+        assert!(labels.contains(&label), "{} should be completed", label);
+    }
+
+    let result = client.request(
+        "textDocument/formatting",
+        json!({
+            "textDocument": { "uri": URI },
+            "options": { "tabSize": 4, "insertSpaces": true },
+        }),
+    );
+    assert_eq!(result[0]["range"], range((0, 0), (12, 0)));
+    assert_eq!(
+        result[0]["newText"],
+        SOURCE.replace("  push x*2", "    push x * 2")
+    );
+
+    // Invalid notifications are logged, rather than stopping the server
+    client.notify(
+        "textDocument/didChange",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "print \"é\" + )\n" }],
+        }),
+    );
+    let diagnostics = client.diagnostics();
+    assert_eq!(diagnostics[0]["message"], "Expected expression, got )");
+    assert_eq!(diagnostics[0]["range"], range((0, 12), (0, 13)));
+
+    assert_eq!(client.request("shutdown", Value::Null), Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.child.wait().unwrap().success());
+    let mut stderr = String::new();
+    client
+        .child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    assert!(
+        stderr.starts_with("Invalid textDocument/didChange notification: "),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,165 @@
+//! How numbers behave in Stacc programs
+
+use stacc::{
+    interpreter::{value::Value, Interpreter, RuntimeError},
+    parser::Parser,
+};
+
+/// The value `source` throws
+fn thrown(source: &str) -> Value {
+    let stmts = Parser::new(source).parse().unwrap();
+    match Interpreter::new().run(&stmts) {
+        Err(RuntimeError::Thrown(value)) => value,
+        result => panic!("{:?} should throw, got {:?}", source, result),
+    }
+}
+
+/// The value of `expr`, which shouldn't fail
+fn eval(expr: &str) -> Value {
+    let source = format!("set result {}\n", expr);
+    let stmts = Parser::new(&source).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&stmts).unwrap();
+    interpreter.variable("result").unwrap()
+}
+
+/// The error from evaluating `expr`
+fn error(expr: &str) -> RuntimeError {
+    let source = format!("push {}\n", expr);
+    let stmts = Parser::new(&source).parse().unwrap();
+    match Interpreter::new().run(&stmts) {
+        Ok(()) => panic!("{:?} should fail", expr),
+        Err(err) => err,
+    }
+}
+
+/// The type and printed form of the value of `expr`
+fn typed(expr: &str) -> (&'static str, String) {
+    let value = eval(expr);
+    (value.type_name(), value.to_string())
+}
+
+fn truthy(expr: &str) -> bool {
+    match thrown(&format!("throw not not {}\n", expr)) {
+        Value::Bool(boolean) => boolean,
+        value => panic!("not should give a bool, got {:?}", value),
+    }
+}
+
+#[test]
+fn zero_is_falsy() {
+    assert!(!truthy("0"));
+    assert!(!truthy("0.0"));
+    assert!(!truthy("-0.0"));
+    assert!(truthy("1"));
+    assert!(truthy("-3"));
+    assert!(truthy("0.5"));
+    assert!(!truthy("0 or 0.0"));
+    assert!(truthy("2 and 0.1"));
+}
+
+#[test]
+fn integer_arithmetic() {
+    assert!(matches!(eval("2 + 3 * 4"), Value::Int(14)));
+    assert!(matches!(eval("7 // 2"), Value::Int(3)));
+    assert!(matches!(eval("-7 // 2"), Value::Int(-4)));
+    assert!(matches!(eval("-7 % 3"), Value::Int(2)));
+    assert!(matches!(eval("3 % -2"), Value::Int(-1)));
+    // True division always gives a float
+    assert!(matches!(eval("7 / 2"), Value::Float(float) if float == 3.5));
+    assert!(matches!(eval("6 / 2"), Value::Float(float) if float == 3.0));
+}
+
+#[test]
+fn integers_grow_instead_of_overflowing() {
+    let max = i64::MAX.to_string();
+    let past_max = (i64::MAX as i128 + 1).to_string();
+    let past_min = (i64::MIN as i128 - 1).to_string();
+
+    assert!(matches!(eval(&format!("{} + 1", max)), Value::BigInt(_)));
+    assert_eq!(
+        typed(&format!("{} + 1", max)),
+        ("integer", past_max.clone())
+    );
+    assert_eq!(typed(&format!("-{} - 2", max)), ("integer", past_min));
+    assert_eq!(
+        typed(&format!("(-{} - 1) // -1", max)),
+        ("integer", past_max)
+    );
+    assert_eq!(
+        typed("2 ** 100"),
+        ("integer", "1267650600228229401496703205376".to_string())
+    );
+
+    // Back in range of an i64 they shrink again
+    assert!(matches!(
+        eval(&format!("{} * 2 // 2", max)),
+        Value::Int(i64::MAX)
+    ));
+    assert!(matches!(eval("2 ** 100 - 2 ** 100 + 1"), Value::Int(1)));
+}
+
+#[test]
+fn integers_too_large_to_compute_are_errors() {
+    for (expr, op) in [
+        ("10 ** 10 ** 10", "exponentiation"),
+        ("1 << (1 << 40)", "left shift"),
+    ] {
+        let err = error(expr);
+        assert!(
+            matches!(err, RuntimeError::IntegerOverflow { op: got } if got == op),
+            "{}: {}",
+            expr,
+            err
+        );
+    }
+}
+
+#[test]
+fn floats_are_contagious() {
+    assert!(matches!(eval("1 + 0.5"), Value::Float(float) if float == 1.5));
+    assert!(matches!(eval("7.5 // 2"), Value::Float(float) if float == 3.0));
+    assert!(matches!(eval("2 ** 100 + 0.5"), Value::Float(_)));
+    assert!(matches!(eval("0.5r + 0.5"), Value::Float(float) if float == 1.0));
+    assert!(matches!(eval("0.1 + 0.2 == 0.3"), Value::Bool(false)));
+}
+
+#[test]
+fn rationals_stay_exact() {
+    assert!(matches!(eval("0.1r + 0.2r == 0.3r"), Value::Bool(true)));
+    assert_eq!(typed("0.1r + 0.2r"), ("rational", "3/10".to_string()));
+    assert_eq!(typed("1 / 3r"), ("rational", "1/3".to_string()));
+    // Even when they come out whole
+    assert_eq!(typed("1 / 3r + 2 / 3r"), ("rational", "1r".to_string()));
+    assert_eq!(typed("0.5r * 2"), ("rational", "1r".to_string()));
+}
+
+#[test]
+fn exponentiation() {
+    assert!(matches!(eval("2 ** 10"), Value::Int(1024)));
+    assert!(matches!(eval("-2 ** 2"), Value::Int(-4)));
+    assert!(matches!(eval("2 ** 3 ** 2"), Value::Int(512)));
+    assert!(matches!(eval("2 ** -1"), Value::Float(float) if float == 0.5));
+    assert!(matches!(eval("0 ** 0"), Value::Int(1)));
+    assert_eq!(typed("0.5r ** -2"), ("rational", "4r".to_string()));
+}
+
+#[test]
+fn zero_to_a_negative_power_divides_by_zero() {
+    for expr in [
+        "0 ** -1",
+        "0.0 ** -1",
+        "0 ** -0.5",
+        "0.0 ** -2.0",
+        "0 ** -(2 ** 100)",
+        "0r ** -1",
+    ] {
+        let err = error(expr);
+        assert!(
+            matches!(err, RuntimeError::DivisionByZero),
+            "{}: {}",
+            expr,
+            err
+        );
+    }
+}
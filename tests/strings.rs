@@ -0,0 +1,124 @@
+//! String literals and the strings they make
+
+use stacc::{
+    interpreter::{value::Value, Interpreter},
+    parser::Parser,
+};
+
+/// The string `expr` evaluates to
+fn string(expr: &str) -> String {
+    result(&format!("set result {}\n", expr))
+}
+
+/// The string `source` sets `result` to
+fn result(source: &str) -> String {
+    let stmts = Parser::new(source).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&stmts).unwrap();
+    match interpreter.variable("result") {
+        Some(Value::String(string)) => string.to_string(),
+        value => panic!("result should be a string, got {:?}", value),
+    }
+}
+
+fn parse_error(source: &str) -> String {
+    match Parser::new(source).parse() {
+        Ok(_) => panic!("{:?} should fail to parse", source),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn escapes() {
+    assert_eq!(
+        string(r#""a\tb\nc\\d\"e\'f\0g\rh""#),
+        "a\tb\nc\\d\"e'f\0g\rh"
+    );
+    assert_eq!(string(r#""\u{1F600} \u{e9}""#), "😀 é");
+}
+
+#[test]
+fn invalid_escapes_are_parse_errors() {
+    assert_eq!(
+        parse_error(r#"print "\q""#),
+        "Parse error at 1:7 - Invalid escape sequence '\\q'"
+    );
+    assert_eq!(
+        parse_error(r#"print "\u{110000}""#),
+        "Parse error at 1:7 - '110000' is not a valid unicode scalar value"
+    );
+    assert_eq!(
+        parse_error(r#"print "\u{zz}""#),
+        "Parse error at 1:7 - 'zz' is not a valid unicode scalar value"
+    );
+}
+
+#[test]
+fn raw_strings_keep_backslashes_and_braces() {
+    assert_eq!(string(r#"r"C:\new\{x}""#), r"C:\new\{x}");
+}
+
+#[test]
+fn triple_quoted_strings() {
+    let literal = "\"\"\"first \"quoted\"\n\\tsecond\"\"\"";
+    assert_eq!(string(literal), "first \"quoted\"\n\tsecond");
+
+    let literal = "r\"\"\"raw \"quoted\" \\n\"\"\"";
+    assert_eq!(string(literal), "raw \"quoted\" \\n");
+}
+
+#[test]
+fn string_literals_inside_interpolations() {
+    let message = "String literals are not allowed inside '{}' in a string, as their '\"' ends it \
+                   (or use '{{' or '\\{' for a literal brace)";
+    assert_eq!(
+        parse_error("print \"{ \"a\" }\"\n"),
+        format!("Parse error at 1:9 - {}", message)
+    );
+    // With no `}` after the string it was just an unescaped brace
+    assert_eq!(
+        parse_error("print \"x{y\"\n"),
+        "Parse error at 1:8 - Unclosed '{' in string, use '{{' or '\\{' for a literal brace"
+    );
+    // A triple-quoted string isn't ended by a single quote
+    assert_eq!(string("\"\"\"{ \"a\" + \"b\" }\"\"\""), "ab");
+}
+
+#[test]
+fn interpolation() {
+    let source = "set name \"Ann\"
+set n 2
+set result \"hello {name}, you have {n + 1} and {n * 1.5}{name}\"
+";
+    assert_eq!(result(source), "hello Ann, you have 3 and 3.0Ann");
+
+    let source = "push \",\"
+push \"a,b\"
+call split
+set parts pop
+set result \"{ parts[1] } of {parts}\"
+";
+    assert_eq!(result(source), "b of [\"a\", \"b\"]");
+}
+
+#[test]
+fn literal_braces() {
+    assert_eq!(string(r#""{{x} \{y\} }""#), "{x} {y} }");
+    assert_eq!(string(r#"r"{x}""#), "{x}");
+}
+
+#[test]
+fn invalid_interpolations_are_parse_errors() {
+    assert_eq!(
+        parse_error("print \"{}\"\n"),
+        "Parse error at 1:7 - Expected expression inside '{}' in string"
+    );
+    assert_eq!(
+        parse_error("print \"{1 +}\"\n"),
+        "Parse error at 1:11 - Unexpected '}' in string, expected expression"
+    );
+    assert_eq!(
+        parse_error("print \"{1 2}\"\n"),
+        "Parse error at 1:10 - Expected operator or terminator, got integer literal"
+    );
+}
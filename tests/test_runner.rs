@@ -0,0 +1,40 @@
+//! Running the `test_*` functions in files with `stacc test`
+
+use std::{fs, process::Command};
+
+#[test]
+fn failures_show_where_the_assertion_is() {
+    let dir = std::env::temp_dir().join(format!("stacc-test-runner-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("maths.stacc");
+    fs::write(
+        &file,
+        "begin test_ok:\n  assert 1 == 1\nend\nbegin test_bad:\n  set x 2\n  assert_eq x, 3, \"x\"\nend\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_stacc"))
+        .arg("test")
+        .arg(&file)
+        .output()
+        .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("::test_ok"), "{}", stdout);
+    // Lines and columns both count from 1
+    assert!(
+        stdout.contains(&format!(
+            "  Assertion error - x: 2 != 3\n    at {}:6:3\n       6 |   assert_eq x, 3, \"x\"\n",
+            file.display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("1 passed, 1 failed in 1 file"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,113 @@
+//! Catching errors with `try`, and throwing them again
+
+use stacc::{
+    interpreter::{limits::Limits, Interpreter, RuntimeError},
+    parser::Parser,
+};
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let stmts = Parser::new(source).parse().unwrap();
+    interpreter.run(&stmts)
+}
+
+/// The global stack, bottom first, as it is printed
+fn stack(interpreter: &Interpreter) -> Vec<String> {
+    let (_, stack) = interpreter.state();
+    stack.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn catching_drops_what_the_try_block_pushed() {
+    let mut interpreter = Interpreter::new();
+    let source = "push 1
+try
+  push 2
+  push 3
+  throw \"oops\"
+catch err
+  push err
+end
+";
+    run(&mut interpreter, source).unwrap();
+    assert_eq!(stack(&interpreter), ["1", "oops"]);
+
+    // Inside a function, only the function's own stack is restored
+    let mut interpreter = Interpreter::new();
+    let source = "begin f: x
+  try
+    push x
+    push x * 2
+    push pop / 0
+  catch err
+    push err.kind
+  end
+end
+push 10
+push 5
+call f
+";
+    run(&mut interpreter, source).unwrap();
+    assert_eq!(stack(&interpreter), ["10", "arithmetic"]);
+}
+
+#[test]
+fn rethrowing_keeps_the_original_error() {
+    let source = "begin f:
+  call f
+end
+try
+  call f
+catch err
+  throw err
+end
+";
+    let mut interpreter = Interpreter::with_limits(Limits {
+        max_call_depth: 8,
+        ..Limits::default()
+    });
+    let err = run(&mut interpreter, source).unwrap_err();
+    assert!(
+        matches!(err, RuntimeError::CallDepthExceeded { limit: 8 }),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        "Limit error - Exceeded the call depth of 8"
+    );
+
+    // Caught again, it is still the same kind of error
+    let source = "try
+  try
+    push 1 // 0
+  catch inner
+    throw inner
+  end
+catch outer
+  set kind outer.kind
+  set message outer.message
+end
+";
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, source).unwrap();
+    assert_eq!(
+        interpreter.variable("kind").unwrap().to_string(),
+        "arithmetic"
+    );
+    assert_eq!(
+        interpreter.variable("message").unwrap().to_string(),
+        "Arithmetic error - Division by zero"
+    );
+}
+
+#[test]
+fn rethrowing_a_thrown_value_throws_the_value() {
+    let source = "try
+  throw 42
+catch err
+  throw err
+end
+";
+    let err = run(&mut Interpreter::new(), source).unwrap_err();
+    assert_eq!(err.to_string(), "Uncaught exception - 42");
+}